@@ -1,53 +1,570 @@
 //! Module responsible for executing actions and returning input to the user
-use std::process::exit;
-use todo_list::TodoList;
-
-///Action responsible for adding an item
-pub fn add(todo: &mut TodoList, item: String) {
-    let b = todo.insert(item);
-    if b {
-        println!("Todo item saved!")
-    } else {
-        println!("Todo item already exist!")
+use crate::args::Args;
+use crate::config::{ColorMode, Config, Markers};
+use crate::error::TodoError;
+use crate::render::Render;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::io::{BufRead, ErrorKind, IsTerminal, Write};
+use std::path::Path;
+use todo_list::{
+    Format, MergeStrategy, Priority, Query, TodoItem, TodoList, ValidationError, FULL_PROGRESS,
+    MIN_COMPLETED_FOR_AVERAGE,
+};
+
+/// Directory where the snapshots of the list are kept
+static HISTORY_DIR: &str = ".todo-history";
+/// How many snapshots are kept before pruning the oldest
+const SNAPSHOTS_KEPT: usize = 10;
+/// Csv file, without extension, where old items are archived if `archive_on_complete` is not set
+static DEFAULT_ARCHIVE: &str = "todo_archive";
+/// All the actions the user can run
+static ACTIONS: &[&str] = &[
+    "add",
+    "archive-old",
+    "archive-to",
+    "assign",
+    "bump",
+    "clear",
+    "compact",
+    "complete",
+    "count",
+    "diff",
+    "doctor",
+    "due",
+    "duplicates",
+    "edit-all",
+    "export-json",
+    "export-md",
+    "forecast",
+    "help",
+    "histogram",
+    "history",
+    "import",
+    "import-list",
+    "interactive",
+    "list",
+    "max-items",
+    "merge",
+    "next",
+    "progress",
+    "random",
+    "remove",
+    "reopen",
+    "rollback",
+    "search",
+    "show",
+    "status",
+    "tag",
+    "tags",
+    "timeline",
+    "update",
+    "view",
+];
+/// How many edits away an unknown action can be from a known one to be suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+/// How many items can be completed at once by a search without confirming with `--yes`
+const MAX_UNCONFIRMED_MATCHES: usize = 5;
+
+/// How the `add` action adds the items
+#[derive(Debug, Default)]
+pub struct AddOptions {
+    /// Who the items are given to
+    pub owner: Option<String>,
+    /// Put the items at the start of the list instead of the end
+    pub at_top: bool,
+    /// When the items should be done, as YYYY-MM-DD
+    pub due: Option<String>,
+    pub priority: Option<String>,
+    /// Add the items already completed
+    pub done: bool,
+    pub tags: Vec<String>,
+    /// Link the items refer to, as an http(s) url
+    pub url: Option<String>,
+    /// Csv file, without extension, where the items added completed are archived
+    pub archive: Option<String>,
+}
+
+///Action responsible for adding items, each one reported on its own, as given by the options
+pub fn add(todo: &mut TodoList, items: &[String], options: AddOptions) {
+    let AddOptions {
+        owner,
+        at_top,
+        due,
+        priority,
+        done,
+        tags,
+        url,
+        archive,
+    } = options;
+    let due = match due.map(|due| parse_date(&due)) {
+        Some(Ok(due)) => Some(due),
+        Some(Err(why)) => {
+            println!("{}", why);
+            return;
+        }
+        None => None,
+    };
+    let priority = match priority.map(|priority| priority.parse::<Priority>()) {
+        Some(Ok(priority)) => Some(priority),
+        Some(Err(why)) => {
+            println!("{}", why);
+            return;
+        }
+        None => None,
+    };
+    let url = match url.map(|url| parse_url(&url)) {
+        Some(Ok(url)) => Some(url),
+        Some(Err(why)) => {
+            println!("{}", why);
+            return;
+        }
+        None => None,
+    };
+
+    // only the items really inserted move the next one down, the existing ones are skipped
+    let mut inserted = Vec::new();
+    for item in items {
+        let index = if at_top { inserted.len() } else { todo.len() };
+        let b = todo.insert_with_state(String::from(item), index, done);
+        if b {
+            let id = todo
+                .get_item_by_description(String::from(item))
+                .map(|value| value.id());
+            inserted.extend(id);
+            if let (Some(id), Some(_)) = (id, &owner) {
+                todo.assign(id, owner.clone());
+            }
+            if let (Some(id), Some(_)) = (id, due) {
+                todo.set_due(id, due);
+            }
+            if let (Some(id), Some(priority)) = (id, priority) {
+                todo.set_priority(id, priority);
+            }
+            for tag in &tags {
+                if let Some(id) = id {
+                    todo.tag(id, tag.clone());
+                }
+            }
+            if let (Some(id), Some(_)) = (id, &url) {
+                todo.set_url(id, url.clone());
+            }
+            if done {
+                archive_completed(
+                    id.and_then(|id| todo.get_item_by_id(id)),
+                    archive.as_deref(),
+                );
+            }
+            println!("Todo item saved! -> {} : {}", id.unwrap_or_default(), item);
+        } else {
+            println!("Todo item already exist! -> {}", item)
+        }
+    }
+
+    // a full list makes room with the older items, not with the ones just added
+    report_evicted(todo.evict_over_capacity_keeping(&inserted));
+}
+
+///Print each item evicted to keep the list within its maximum number of items
+fn report_evicted(evicted: Vec<TodoItem>) {
+    for value in evicted {
+        println!(
+            "The list is full, todo item evicted -> {} : {}",
+            value.id(),
+            value.description()
+        )
+    }
+}
+
+///Parse a date given by the user, as YYYY-MM-DD
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("The given date: {} is not a YYYY-MM-DD date!", date))
+}
+
+///Parse the tags given by the user, separated by commas, like "urgent, high prio"
+///Each tag is trimmed, keeping the spaces inside it, and the empty ones are left out
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+///Parse a link given by the user, which must be an http or https url with a host
+pub fn parse_url(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+    let host = rest.map(|rest| rest.split(['/', '?', '#']).next().unwrap_or_default());
+    match host {
+        Some(host) if !host.is_empty() && !url.contains(char::is_whitespace) => {
+            Ok(String::from(url))
+        }
+        _ => Err(format!(
+            "The given url: {} is not like https://example.com!",
+            url
+        )),
+    }
+}
+
+///Parse a duration given by the user, like 45m, 2h or 1h30m, as minutes
+pub fn parse_minutes(duration: &str) -> Result<u32, String> {
+    let invalid = || {
+        format!(
+            "The given duration: {} is not like 45m, 2h or 1h30m!",
+            duration
+        )
+    };
+    let mut minutes: u32 = 0;
+    let mut number = String::new();
+    for c in duration.trim().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value: u32 = number.parse().map_err(|_| invalid())?;
+                let value = if c == 'h' {
+                    value.checked_mul(60)
+                } else {
+                    Some(value)
+                };
+                minutes = value
+                    .and_then(|value| minutes.checked_add(value))
+                    .ok_or_else(invalid)?;
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || duration.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok(minutes)
+}
+
+///Action responsible to set the due date of an item, or `none` to remove it
+pub fn due(todo: &mut TodoList, item: &str, date: Option<&str>) -> bool {
+    let id: u32 = match item.trim().parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("The given id: {} is not a number!", item);
+            return false;
+        }
+    };
+    let due = match date.map(str::trim) {
+        None | Some("none") => None,
+        Some(date) => match parse_date(date) {
+            Ok(due) => Some(due),
+            Err(why) => {
+                println!("{}", why);
+                return false;
+            }
+        },
+    };
+
+    if !todo.set_due(id, due) {
+        println!("There is no item with the given id: {} !", id);
+        return false;
+    }
+    match due {
+        Some(due) => println!("Todo item {} is due on {}!", id, due),
+        None => println!("Todo item {} has no due date now!", id),
+    }
+    true
+}
+
+///Action responsible to raise the priority of an item one level, or lower it if `down`
+///Return if the priority changed
+pub fn bump(todo: &mut TodoList, item: &str, down: bool) -> bool {
+    let id: u32 = match item.trim().parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("The given id: {} is not a number!", item);
+            return false;
+        }
+    };
+
+    match todo.adjust_priority(id, if down { -1 } else { 1 }) {
+        Some((before, after)) if before == after => {
+            println!(
+                "Todo item {} already has {} priority, nothing to do!",
+                id, after
+            );
+            false
+        }
+        Some((before, after)) => {
+            println!(
+                "Todo item {} priority changed from {} to {}!",
+                id, before, after
+            );
+            true
+        }
+        None => {
+            println!("There is no item with the given id: {} !", id);
+            false
+        }
+    }
+}
+
+///Action responsible to set the progress of an item, as a percentage
+///The value is absolute, like `40`, or relative to the current one, like `+25` or `-10`
+///Reaching 100% completes the item, which is then appended to the `archive` csv file, if given
+///Return if the item exists and the value is valid
+pub fn progress(
+    todo: &mut TodoList,
+    item: &str,
+    value: Option<&str>,
+    archive: Option<&str>,
+) -> bool {
+    let id: u32 = match item.trim().parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("The given id: {} is not a number!", item);
+            return false;
+        }
+    };
+    let value = match value {
+        Some(value) => value.trim(),
+        None => {
+            println!("Please specify the progress, like 40, +25 or -10");
+            return false;
+        }
+    };
+    let (current, was_done) = match todo.get_item_by_id(id) {
+        Some(value) => (value.progress() as i32, value.is_done()),
+        None => {
+            println!("There is no item with the given id: {} !", id);
+            return false;
+        }
+    };
+
+    let relative = value.starts_with(['+', '-']);
+    let progress = match value.parse::<i32>() {
+        Ok(delta) if relative => current + delta,
+        Ok(progress) => progress,
+        Err(_) => {
+            println!("The given progress: {} is not a number!", value);
+            return false;
+        }
+    };
+    let progress = progress.clamp(0, FULL_PROGRESS as i32) as u8;
+
+    todo.set_progress(id, progress);
+    match todo.get_item_by_id(id) {
+        Some(value) if value.is_done() => {
+            println!("Todo item {} progress is {}%, it is done!", id, progress);
+            if !was_done {
+                archive_completed(Some(value), archive)
+            }
+        }
+        _ => println!("Todo item {} progress is {}%!", id, progress),
+    }
+    true
+}
+
+///Action responsible to set the maximum number of items of the list, or `off` to remove it
+pub fn max_items(todo: &mut TodoList, item: &str) -> bool {
+    if item.trim() == "off" {
+        todo.set_max_items(None);
+        println!("The list has no maximum number of items now!");
+        return true;
+    }
+
+    match item.trim().parse::<usize>() {
+        Ok(max_items) if max_items > 0 => {
+            todo.set_max_items(Some(max_items));
+            let evicted = todo.evict_over_capacity();
+            println!(
+                "The list keeps at most {} item(s), {} evicted!",
+                max_items,
+                evicted.len()
+            );
+            true
+        }
+        _ => {
+            println!("The given maximum: {} is not a positive number!", item);
+            false
+        }
     }
 }
 
 ///Action responsible for removing an item according to an description
-pub fn remove(todo: &mut TodoList, item: String) {
+///With `json` the removed item is printed as json, instead of a message
+pub fn remove(todo: &mut TodoList, item: String, json: bool, pretty: bool) {
+    println!("{}", removal(todo, &item, json, pretty))
+}
+
+///Remove an item according to an id or description
+///Return the message to print, or the removed item as json if `json`
+fn removal(todo: &mut TodoList, item: &str, json: bool, pretty: bool) -> String {
     use std::num::ParseIntError;
-    let number_id: Result<u32, ParseIntError> = String::from(&item).trim().parse();
-    match number_id {
-        Ok(id) => {
-            let result = todo.remove_by_id(id);
-            match result {
-                Some(value) => {
-                    println!(
-                        "Todo item deleted with success! -> {} : {}",
-                        id,
-                        value.description()
-                    )
-                }
-                None => println!("There is no item with the given id: {} !", id),
+    let number_id: Result<u32, ParseIntError> = item.trim().parse();
+    let result = match number_id {
+        Ok(id) => todo.remove_by_id(id),
+        Err(_) => todo.remove_by_description(String::from(item)),
+    };
+    match (result, number_id) {
+        (Some(value), _) if json => json_string(&[value], pretty),
+        (Some(value), _) => format!(
+            "Todo item deleted with success! -> {} : {}",
+            value.id(),
+            value.description()
+        ),
+        (None, Ok(id)) => format!("There is no item with the given id: {} !", id),
+        (None, Err(_)) => format!("There is no item with the given description: {} !", item),
+    }
+}
+
+///Action responsible for removing all the completed items
+pub fn clear(todo: &mut TodoList) {
+    let count = todo.remove_completed();
+    println!("{} completed todo item(s) deleted with success!", count)
+}
+
+/// How a dry run shows what would change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DryRun {
+    Text,
+    Json,
+}
+
+impl DryRun {
+    ///The dry run given by `--dry-run`, or by `--dry-run-format` which implies it
+    fn parse(flag: bool, format: Option<String>) -> Result<Option<DryRun>, TodoError> {
+        match format.as_deref().map(str::trim) {
+            Some("text") => Ok(Some(DryRun::Text)),
+            Some("json") => Ok(Some(DryRun::Json)),
+            Some(_) => Err(TodoError::InvalidValue(
+                String::from("--dry-run-format"),
+                format.unwrap_or_default(),
+            )),
+            None if flag => Ok(Some(DryRun::Text)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Preview of what an action would change, printed by a json dry run
+#[derive(Serialize)]
+struct Preview<T: Serialize> {
+    action: &'static str,
+    #[serde(flatten)]
+    changes: T,
+}
+
+impl<T: Serialize> Preview<T> {
+    fn of(action: &'static str, changes: T) -> Preview<T> {
+        Preview { action, changes }
+    }
+}
+
+/// The items a remove would delete
+#[derive(Serialize)]
+struct RemovePreview<'a> {
+    count: usize,
+    items: Vec<&'a TodoItem>,
+}
+
+///The preview of removing the items that match the query
+fn remove_preview<'a>(todo: &'a TodoList, query: &Query) -> Preview<RemovePreview<'a>> {
+    let items = todo.query(query);
+    let preview = RemovePreview {
+        count: items.len(),
+        items,
+    };
+    Preview::of("remove", preview)
+}
+
+///Action responsible for deleting all the items that match the query
+///With `dry_run` the items are only listed, and nothing is deleted
+///With `json` the deleted items are printed as json, instead of a message
+///Return if anything was deleted
+pub fn remove_matching(
+    todo: &mut TodoList,
+    query: &Query,
+    dry_run: Option<DryRun>,
+    json: bool,
+    pretty: bool,
+    render: &Render,
+) -> bool {
+    match dry_run {
+        Some(DryRun::Text) => {
+            for line in dry_run_lines(todo, query, render) {
+                println!("{}", line);
             }
+            return false;
         }
-        Err(_) => {
-            let result = todo.remove_by_description(String::from(&item));
-            match result {
-                Some(value) => {
+        Some(DryRun::Json) => {
+            print_json(&remove_preview(todo, query), pretty);
+            return false;
+        }
+        None => {}
+    }
+
+    let removed = todo.remove_matching(query);
+    if json {
+        print_json(&removed, pretty);
+    } else {
+        println!("{} todo item(s) deleted with success!", removed.len());
+    }
+    !removed.is_empty()
+}
+
+///The lines listing the items that removing the ones matching the query would delete
+fn dry_run_lines(todo: &TodoList, query: &Query, render: &Render) -> Vec<String> {
+    let items = todo.query(query);
+    let mut lines = render.table_lines(&items);
+    lines.push(format!("{} todo item(s) would be deleted!", items.len()));
+    lines
+}
+
+///Action responsible for trimming the descriptions, removing duplicates and renumbering
+///Return if anything changed
+///With `dry_run` the changes are only shown, on a copy of the list
+pub fn compact(todo: &mut TodoList, dry_run: Option<DryRun>, pretty: bool) -> bool {
+    if dry_run.is_some() {
+        let report = todo.clone().compact();
+        match dry_run {
+            Some(DryRun::Json) => print_json(&Preview::of("compact", report), pretty),
+            _ => {
+                println!("{} description(s) would be trimmed", report.trimmed);
+                for value in &report.removed {
                     println!(
-                        "Todo item deleted with success! -> {} : {}",
+                        "Duplicated todo item would be deleted -> {} : {}",
                         value.id(),
                         value.description()
-                    )
+                    );
                 }
-                None => println!("There is no item with the given description: {} !", item),
+                println!("{} todo item(s) would be renumbered", report.renumbered);
             }
         }
+        return false;
+    }
+
+    let report = todo.compact();
+    if report.is_empty() {
+        println!("The todo list is already compact!");
+        return false;
+    }
+
+    println!("{} description(s) trimmed", report.trimmed);
+    for value in &report.removed {
+        println!(
+            "Duplicated todo item deleted -> {} : {}",
+            value.id(),
+            value.description()
+        );
     }
+    println!("{} todo item(s) renumbered", report.renumbered);
+    true
 }
 
 ///Action responsible for update an item according to an id or a description
-pub fn update(todo: &mut TodoList, item: String) {
+///A completed item is appended to the `archive` csv file, if given
+pub fn update(todo: &mut TodoList, item: String, archive: Option<&str>) {
     use std::num::ParseIntError;
     let number_id: Result<u32, ParseIntError> = String::from(&item).trim().parse();
     match number_id {
@@ -55,7 +572,10 @@ pub fn update(todo: &mut TodoList, item: String) {
             let result = todo.update_todo_item_id(id);
             match result {
                 Some(value) => {
-                    println!("Todo item update with success! -> {} : {}", id, value)
+                    println!("Todo item update with success! -> {} : {}", id, value);
+                    if value {
+                        archive_completed(todo.get_item_by_id(id), archive)
+                    }
                 }
                 None => println!("There is no item with the given id: {} !", id),
             }
@@ -64,7 +584,10 @@ pub fn update(todo: &mut TodoList, item: String) {
             let result = todo.update_todo_item_description(String::from(&item));
             match result {
                 Some(value) => {
-                    println!("Todo item update with success! -> {} : {}", &item, value)
+                    println!("Todo item update with success! -> {} : {}", &item, value);
+                    if value {
+                        archive_completed(todo.get_item_by_description(item), archive)
+                    }
                 }
                 None => println!("There is no item with the given description: {} !", item),
             }
@@ -72,8 +595,274 @@ pub fn update(todo: &mut TodoList, item: String) {
     }
 }
 
+///The id of the item given by the user, as an id or as a description
+fn resolve_id(todo: &TodoList, item: &str) -> Option<u32> {
+    match item.trim().parse::<u32>() {
+        Ok(id) => Some(id),
+        Err(_) => todo
+            .get_item_by_description(String::from(item))
+            .map(|value| value.id()),
+    }
+}
+
+///Action responsible for completing an item, with an optional note about the outcome
+///and the time `spent` on it, like 45m, 2h or 1h30m
+///A completed item is appended to the `archive` csv file, if given
+///Return if the item was completed
+pub fn complete(
+    todo: &mut TodoList,
+    item: &str,
+    note: Option<String>,
+    spent: Option<String>,
+    archive: Option<&str>,
+) -> bool {
+    let spent = match spent.map(|spent| parse_minutes(&spent)) {
+        Some(Ok(minutes)) => Some(minutes),
+        Some(Err(why)) => {
+            println!("{}", why);
+            return false;
+        }
+        None => None,
+    };
+    let result = resolve_id(todo, item).and_then(|id| Some((id, todo.complete_by_id(id, note)?)));
+    match result {
+        Some((id, true)) => {
+            if let Some(minutes) = spent {
+                todo.set_actual_minutes(id, minutes);
+            }
+            let value = todo.get_item_by_id(id);
+            let description = value.map(|value| value.description()).unwrap_or(item);
+            println!(
+                "Todo item completed with success! -> {} : {}",
+                id, description
+            );
+            archive_completed(value, archive);
+            true
+        }
+        Some((id, false)) => {
+            println!("Todo item {} is already done!", id);
+            false
+        }
+        None => {
+            println!(
+                "There is no item with the given id or description: {} !",
+                item
+            );
+            false
+        }
+    }
+}
+
+///Action responsible for completing every pending item whose description contains the term
+///More than `MAX_UNCONFIRMED_MATCHES` items are only completed if `yes` is given
+///Return if any item was completed
+pub fn complete_matching(
+    todo: &mut TodoList,
+    term: &str,
+    case_sensitive: bool,
+    yes: bool,
+    note: Option<String>,
+    archive: Option<&str>,
+) -> bool {
+    let ids: Vec<u32> = todo
+        .search(term, case_sensitive)
+        .into_iter()
+        .filter(|value| !value.is_done())
+        .map(|value| value.id())
+        .collect();
+    if ids.len() > MAX_UNCONFIRMED_MATCHES && !yes {
+        println!(
+            "{} todo items match: {}, use --yes to complete all of them",
+            ids.len(),
+            term
+        );
+        return false;
+    }
+
+    for id in &ids {
+        if todo.complete_by_id(*id, note.clone()) == Some(true) {
+            archive_completed(todo.get_item_by_id(*id), archive);
+        }
+    }
+    println!("{} todo item(s) completed with success!", ids.len());
+    !ids.is_empty()
+}
+
+///Action responsible for reopening a completed item
+///Return if the item was reopened
+pub fn reopen(todo: &mut TodoList, item: &str) -> bool {
+    match resolve_id(todo, item).and_then(|id| Some((id, todo.reopen_by_id(id)?))) {
+        Some((id, true)) => {
+            let value = todo.get_item_by_id(id);
+            let description = value.map(|value| value.description()).unwrap_or(item);
+            println!(
+                "Todo item reopened with success! -> {} : {}",
+                id, description
+            );
+            true
+        }
+        Some((id, false)) => {
+            println!("Todo item {} is not done yet!", id);
+            false
+        }
+        None => {
+            println!(
+                "There is no item with the given id or description: {} !",
+                item
+            );
+            false
+        }
+    }
+}
+
+///Append a completed item to the archive csv file, if there is one
+///Failing to do it only warns, the item stays completed
+fn archive_completed(item: Option<&TodoItem>, archive: Option<&str>) {
+    if let (Some(item), Some(archive)) = (item, archive) {
+        if let Err(why) = item.append_csv(archive) {
+            println!(
+                "Warning: could not archive the item in {}.csv: {}",
+                archive, why
+            )
+        }
+    }
+}
+
+///Action responsible to move all the completed items to another list file
+///The items with the same description as one in the target stay in this list
+///The move is done on a copy, so nothing leaves this list if the target can't be saved
+///Return if anything was moved
+pub fn archive_to(todo: &mut TodoList, filename: &str, target: &str) -> bool {
+    let target = target.strip_suffix(".json").unwrap_or(target);
+    let same_file = match (
+        std::fs::canonicalize(format!("{}.json", filename)),
+        std::fs::canonicalize(format!("{}.json", target)),
+    ) {
+        (Ok(source_path), Ok(target_path)) => source_path == target_path,
+        _ => filename == target,
+    };
+    if same_file {
+        println!("The target list is this one, nothing to do!");
+        return false;
+    }
+
+    let mut other = match TodoList::read_json(target) {
+        Ok(other) => other,
+        Err(why) if why.kind() == ErrorKind::NotFound => TodoList::build(),
+        Err(why) => {
+            println!("Could not read {}: {}", target, why);
+            return false;
+        }
+    };
+    let mut remaining = todo.clone();
+    let (moved, stayed) = remaining.move_all_completed_to(&mut other);
+    let evicted = other.evict_over_capacity();
+    if moved > 0 {
+        if let Err(why) = other.save_json(target) {
+            println!(
+                "Could not save {}: {}, the items stay in this list",
+                target, why
+            );
+            return false;
+        }
+        *todo = remaining;
+    }
+    for value in evicted {
+        println!(
+            "{}.json is full, todo item evicted -> {} : {}",
+            target,
+            value.id(),
+            value.description()
+        )
+    }
+    println!(
+        "{} completed todo item(s) moved to {}.json, {} already there",
+        moved, target, stayed
+    );
+    moved > 0
+}
+
+///Action responsible to move the items completed more than `days` before `now` to the archive
+///They are appended to the csv file of `archive_on_complete`, or else of `DEFAULT_ARCHIVE`
+///Items that couldn't be archived stay in the list
+///Return how many were archived
+pub fn archive_old(todo: &mut TodoList, days: i64, config: &Config, now: DateTime<Utc>) -> usize {
+    let archive = archive_file(config);
+    let ids: Vec<u32> = todo
+        .completed_before(now - Duration::days(days))
+        .into_iter()
+        .map(|value| value.id())
+        .collect();
+
+    let mut archived = 0;
+    for id in ids {
+        let appended = match todo
+            .get_item_by_id(id)
+            .map(|value| value.append_csv(archive))
+        {
+            Some(Ok(())) => true,
+            Some(Err(why)) => {
+                eprintln!(
+                    "Warning: could not archive the item in {}.csv: {}",
+                    archive, why
+                );
+                false
+            }
+            None => false,
+        };
+        if appended && todo.remove_by_id(id).is_some() {
+            archived += 1;
+        }
+    }
+    archived
+}
+
+///The csv file, without extension, where the old completed items are archived
+fn archive_file(config: &Config) -> &str {
+    config
+        .archive_on_complete
+        .as_deref()
+        .unwrap_or(DEFAULT_ARCHIVE)
+}
+
+///Archive the old completed items, if `auto_archive_days` is configured
+///It runs before every action, so it reports to stderr to leave their output alone
+///Return if anything was archived
+fn auto_archive(todo: &mut TodoList, config: &Config, now: DateTime<Utc>) -> bool {
+    let days = match config.auto_archive_days {
+        Some(days) if days < 0 => {
+            eprintln!(
+                "The auto_archive_days {} is negative, nothing is archived",
+                days
+            );
+            return false;
+        }
+        Some(days) => days,
+        None => return false,
+    };
+    let archived = archive_old(todo, days, config, now);
+    if archived > 0 {
+        eprintln!(
+            "{} completed todo item(s) archived in {}.csv!",
+            archived,
+            archive_file(config)
+        );
+    }
+    archived > 0
+}
+
 ///Action responsible to save the TodoList to a file
+///The previous version of the file is kept as a snapshot
 pub fn save(todo: &mut TodoList, filename: &str) {
+    save_in(todo, filename, HISTORY_DIR)
+}
+
+///Save the TodoList to a file, keeping the previous version in the `history` directory
+fn save_in(todo: &mut TodoList, filename: &str, history: &str) {
+    if let Err(why) = TodoList::snapshot_json(filename, history, SNAPSHOTS_KEPT) {
+        println!("Could not keep a snapshot of the list: {}", why)
+    }
+
     // match todo.save_csv(filename) {
     match todo.save_json(filename) {
         Ok(_) => {}
@@ -82,68 +871,1851 @@ pub fn save(todo: &mut TodoList, filename: &str) {
 }
 
 ///Action responsible to read the TodoList to a file
-pub fn read(filename: &str) -> TodoList {
+///A missing file gives an empty list, to be created when saved, or is an error if not `create`
+///A file that can't be parsed is an error if `strict`, or gives an empty list with a warning
+pub fn read(filename: &str, strict: bool, create: bool) -> Result<TodoList, TodoError> {
+    if !create && !Path::new(&format!("{}.json", filename)).exists() {
+        return Err(TodoError::NotFound(String::from(filename)));
+    }
     // TodoList::read_csv(filename).expect("Initialisation of db failed")
-    let result = TodoList::read_json(filename);
+    let todo = if strict {
+        match TodoList::load(filename, Format::Json) {
+            Ok(todo) => todo,
+            Err(why) if why.kind() == ErrorKind::NotFound => TodoList::build(),
+            Err(why) => return Err(TodoError::Parse(why.to_string())),
+        }
+    } else {
+        TodoList::load_or_default(filename, Format::Json)
+    };
+    if !todo.verify_checksum() {
+        eprintln!(
+            "Warning: the checksum of {} doesn't match, the file may be corrupted or edited",
+            filename
+        )
+    }
+    Ok(todo)
+}
+
+///Action responsible to render any json value, pretty or compact
+pub fn print_json<T: Serialize>(value: &T, pretty: bool) {
+    println!("{}", json_string(value, pretty))
+}
+
+///Render any json value, pretty or compact in a single line
+fn json_string<T: Serialize>(value: &T, pretty: bool) -> String {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    json.unwrap_or("Nothing".to_string())
+}
 
-    if result.is_ok() {
-        result.unwrap()
+///The format used when none is given: the table on a terminal, json when piped
+fn default_format(is_terminal: bool) -> &'static str {
+    if is_terminal {
+        "text"
     } else {
-        TodoList::build()
+        "json"
     }
 }
 
 ///Action responsible to given all the TodoList
-pub fn print_json_pretty(todo: &TodoList) {
-    println!(
-        "{}",
-        &todo.to_json_pretty().unwrap_or("Nothing".to_string())
-    )
+///Without a `format`, it is chosen by the output being a terminal or not
+pub fn show(todo: &TodoList, format: Option<&str>, pretty: bool, render: &Render) {
+    let format = format.unwrap_or_else(|| default_format(std::io::stdout().is_terminal()));
+    match format {
+        "json" => print_json(todo, pretty),
+        "text" => {
+            render.table(&todo.items());
+            if render.header {
+                println!("{}", todo.summary_string());
+            }
+        }
+        _ => println!("The given format: {} is invalid!", format),
+    }
 }
 
-///Action responsible to given all the TodoList
-pub fn print_json(todo: &TodoList) {
-    println!("{}", &todo.to_json().unwrap_or("Nothing".to_string()))
+///Action responsible to list all the actions the user can run
+pub fn help() {
+    println!("Usage: todo <action> [item] [options]");
+    println!("Actions: {}", ACTIONS.join(", "));
 }
 
-pub fn render_cli(filename: &str) {
-    let argv = std::env::args().len();
+///Action responsible to print the TodoList as a markdown checklist
+pub fn export_markdown(todo: &TodoList, include_ids: bool) {
+    print!("{}", todo.to_markdown(include_ids))
+}
 
-    if argv < 2 {
-        println!("Please specify an action");
-        exit(0);
+///Action responsible to print all the items as a json array, written one item at a time
+///With `anonymize` the descriptions are replaced by `task-{id}`, and the tags and the owners
+///are removed too if `scrub_tags` and `scrub_owners` are given
+pub fn export_json(todo: &TodoList, anonymize: bool, scrub_tags: bool, scrub_owners: bool) {
+    let anonymized;
+    let todo = if anonymize {
+        anonymized = todo.anonymized(scrub_tags, scrub_owners);
+        &anonymized
+    } else {
+        todo
+    };
+    let stdout = std::io::stdout();
+    match todo.items_json_stream(stdout.lock()) {
+        Ok(()) => println!(),
+        Err(why) => println!("Couldn't export the items: {}", why),
     }
+}
 
-    let action = std::env::args().nth(1).expect("Please specify an action");
-    let mut item = "".to_string();
-    // actions that only need 2 args
-    let actions_only_2 = ["help", "show"];
+///Action responsible to show how many items were completed in each of the last weeks
+pub fn timeline(todo: &TodoList, weeks: Option<String>) {
+    let weeks: i64 = match weeks.map(|weeks| weeks.trim().parse()) {
+        Some(Ok(weeks)) if weeks > 0 => weeks,
+        Some(_) => {
+            println!("The given number of weeks is not a positive number!");
+            return;
+        }
+        None => 8,
+    };
 
-    if !actions_only_2.contains(&action.as_str()) && argv < 3 {
-        println!("Please specify an item");
-        exit(0);
-    } else if !actions_only_2.contains(&action.as_str()) {
-        item = std::env::args().nth(2).expect("Please specify an item");
+    let today = Utc::now().date_naive();
+    let from = today - Duration::weeks(weeks - 1);
+    for (week, count) in todo.stats_timeline(from, today) {
+        println!("{}-W{:02}: {}", week.year(), week.week(), count);
     }
-    // println!("{:?}, {:?}", action, item);
+}
 
-    let mut todo = read(filename);
-    let mut changes = true;
+///Action responsible to show how long the completed items took, grouped in ranges
+pub fn histogram(todo: &TodoList) {
+    for (label, count) in todo.completion_time_buckets() {
+        let line = format!("{:>5}: {:<4} {}", label, count, "#".repeat(count));
+        println!("{}", line.trim_end());
+    }
+}
 
-    if action == "add" {
-        add(&mut todo, item);
-    } else if action == "remove" {
-        remove(&mut todo, String::from(&item));
-    } else if action == "update" {
-        update(&mut todo, String::from(&item));
-    } else if action == "show" {
-        print_json_pretty(&todo)
+/// An item with the fields computed when listing it, not kept in the file
+#[derive(Serialize)]
+struct WithComputed<'a> {
+    #[serde(flatten)]
+    item: &'a TodoItem,
+    overdue: bool,
+}
+
+///Action responsible to list all the items, as a table or as json
+///Only the items that match the query are listed, or just counted if `count_only`
+///With `with_computed` the json items also get the computed fields, like `overdue`
+pub fn list(
+    todo: &TodoList,
+    query: &Query,
+    format: &str,
+    pretty: bool,
+    count_only: bool,
+    with_computed: bool,
+    render: &Render,
+) {
+    if count_only {
+        println!("{}", count_line(todo, query));
+        return;
+    }
+    let items = todo.query(query);
+
+    match format {
+        "json" if with_computed => {
+            let items: Vec<WithComputed> = items
+                .into_iter()
+                .map(|item| WithComputed {
+                    item,
+                    overdue: item.is_overdue(render.today),
+                })
+                .collect();
+            print_json(&items, pretty)
+        }
+        "json" => print_json(&items, pretty),
+        "text" => {
+            render.table(&items);
+            if render.header {
+                println!("{}", todo.summary_string());
+            }
+        }
+        _ => println!("The given format: {} is invalid!", format),
+    }
+}
+
+///The line printed by `list --count-only`, only the number of items that match the query
+fn count_line(todo: &TodoList, query: &Query) -> String {
+    todo.query(query).len().to_string()
+}
+
+///The current user, given by TODO_USER or else by USER
+fn current_user() -> Option<String> {
+    ["TODO_USER", "USER"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|user| !user.trim().is_empty())
+}
+
+///Action responsible to give an item to an owner
+pub fn assign(todo: &mut TodoList, item: String, owner: Option<String>) {
+    match item.trim().parse::<u32>() {
+        Ok(id) => {
+            if !todo.assign(id, owner.clone()) {
+                println!("There is no item with the given id: {} !", id)
+            } else {
+                match owner {
+                    Some(owner) => println!("Todo item {} assigned to {}!", id, owner),
+                    None => println!("Todo item {} has no owner now!", id),
+                }
+            }
+        }
+        Err(_) => println!("The given id: {} is not a number!", item),
+    }
+}
+
+///Action responsible to add a tag to an item
+///The existing tags are suggested when the tag is a new one
+pub fn tag(todo: &mut TodoList, item: String, tag: Option<&str>) -> bool {
+    let tag = match tag.map(str::trim) {
+        Some(tag) if !tag.is_empty() => String::from(tag),
+        _ => {
+            println!("Please specify a tag");
+            return false;
+        }
+    };
+    let id: u32 = match item.trim().parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("The given id: {} is not a number!", item);
+            return false;
+        }
+    };
+
+    let existing = todo.all_tags();
+    if !todo.tag(id, String::from(&tag)) {
+        println!("There is no item with the given id: {} !", id);
+        return false;
+    }
+
+    println!("Todo item {} tagged with {}!", id, tag);
+    if !existing.is_empty() && !existing.contains(&tag) {
+        let existing: Vec<&str> = existing.iter().map(|tag| tag.as_str()).collect();
+        println!(
+            "{} is a new tag, the existing ones are: {}",
+            tag,
+            existing.join(", ")
+        );
+    }
+    true
+}
+
+///Action responsible to rename a tag in all the items
+///Return if any item changed
+pub fn rename_tag(todo: &mut TodoList, old: Option<&str>, new: Option<&str>) -> bool {
+    let (old, new) = match (old.map(str::trim), new.map(str::trim)) {
+        (Some(old), Some(new)) if !old.is_empty() && !new.is_empty() => (old, new),
+        _ => {
+            println!("Please specify the tag to rename and its new name");
+            return false;
+        }
+    };
+
+    let changed = todo.rename_tag(old, new);
+    println!(
+        "Tag {} renamed to {} in {} todo item(s)!",
+        old, new, changed
+    );
+    changed > 0
+}
+
+///Action responsible to list all the distinct tags, with how many items use them
+///With `cloud` the most used tags come first, instead of sorting them by name
+pub fn tags(todo: &TodoList, cloud: bool) {
+    let counts: Vec<(String, usize)> = if cloud {
+        todo.tag_cloud()
     } else {
-        changes = false;
-        println!("The given command: {} is invalid!", action);
+        todo.tag_counts().into_iter().collect()
+    };
+    for (tag, count) in counts {
+        println!("{} ({})", tag, count);
     }
+}
 
-    if changes {
-        save(&mut todo, filename)
+///Action responsible to merge the items of another list file into this one
+///Items with the same description are solved by the `strategy`, skipping them by default
+///Return if anything changed
+pub fn merge(todo: &mut TodoList, other: &str, strategy: Option<String>) -> bool {
+    let strategy = match strategy.map(|strategy| strategy.parse::<MergeStrategy>()) {
+        Some(Ok(strategy)) => strategy,
+        Some(Err(why)) => {
+            println!("{}", why);
+            return false;
+        }
+        None => MergeStrategy::default(),
+    };
+    let other = match read_other(other) {
+        Some(other) => other,
+        None => return false,
+    };
+
+    let report = todo.merge(other, strategy);
+    println!(
+        "{} todo item(s) added, {} replaced and {} skipped!",
+        report.added, report.replaced, report.skipped
+    );
+    report_evicted(todo.evict_over_capacity());
+    report.added + report.replaced > 0
+}
+
+/// Copy the items of another list into this one, skipping the descriptions already here
+/// The other list file is left untouched
+pub fn import_list(todo: &mut TodoList, other: &str) -> bool {
+    let other = match read_other(other) {
+        Some(other) => other,
+        None => return false,
+    };
+
+    let report = todo.merge(other, MergeStrategy::Skip);
+    println!(
+        "{} todo item(s) added and {} skipped!",
+        report.added, report.skipped
+    );
+    report_evicted(todo.evict_over_capacity());
+    report.added > 0
+}
+
+/// Read the other list given by the user, with or without the extension
+fn read_other(other: &str) -> Option<TodoList> {
+    let name = other.strip_suffix(".json").unwrap_or(other);
+    match TodoList::read_json(name) {
+        Ok(other) => Some(other),
+        Err(why) => {
+            println!("Could not read {}: {}", name, why);
+            None
+        }
+    }
+}
+
+///Action responsible to add the items of a csv file, in the order of its lines
+///With `reverse` they are added from the last line to the first
+///Return if anything was added
+pub fn import(todo: &mut TodoList, file: &str, reverse: bool) -> bool {
+    let name = file.strip_suffix(".csv").unwrap_or(file);
+    match todo.import_csv(name, reverse) {
+        Ok(report) => {
+            println!(
+                "{} todo item(s) imported and {} skipped!",
+                report.added, report.skipped
+            );
+            report_evicted(todo.evict_over_capacity());
+            report.added > 0
+        }
+        Err(why) => {
+            println!("Could not read {}: {}", name, why);
+            false
+        }
+    }
+}
+
+///Action responsible to show the differences between two saved lists
+pub fn diff(old: &str, new: Option<&str>, format: &str, pretty: bool) {
+    let new = match new {
+        Some(new) => new,
+        None => {
+            println!("Please specify the list to compare with");
+            return;
+        }
+    };
+    let read_list = |name: &str| {
+        let name = name.strip_suffix(".json").unwrap_or(name);
+        TodoList::read_json(name).map_err(|why| println!("Could not read {}: {}", name, why))
+    };
+    let (old, new) = match (read_list(old), read_list(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        _ => return,
+    };
+
+    let diff = old.diff(&new);
+    match format {
+        "json" => print_json(&diff, pretty),
+        "text" if diff.is_empty() => println!("The lists have the same items!"),
+        "text" => {
+            for value in &diff.added {
+                println!("+ {} : {}", value.id(), value.description());
+            }
+            for value in &diff.removed {
+                println!("- {} : {}", value.id(), value.description());
+            }
+            for change in &diff.changed {
+                println!(
+                    "~ {} : {} ({}) -> {} ({})",
+                    change.after.id(),
+                    change.before.description(),
+                    if change.before.is_done() {
+                        "done"
+                    } else {
+                        "pending"
+                    },
+                    change.after.description(),
+                    if change.after.is_done() {
+                        "done"
+                    } else {
+                        "pending"
+                    },
+                );
+            }
+        }
+        _ => println!("The given format: {} is invalid!", format),
+    }
+}
+
+///Action responsible to show the pending item to work on next
+pub fn next(todo: &TodoList) {
+    match todo.next_task() {
+        Some(value) => println!("{} : {}", value.id(), value.description()),
+        None => println!("There are no pending items, everything is done!"),
+    }
+}
+
+///Action responsible to pick a random pending item to work on
+///The same `seed` always picks the same item of the same list
+pub fn random(todo: &TodoList, seed: Option<String>) {
+    let mut rng = match seed.map(|seed| seed.trim().parse::<u64>()) {
+        Some(Ok(seed)) => StdRng::seed_from_u64(seed),
+        Some(Err(_)) => {
+            println!("The given seed is not a number!");
+            return;
+        }
+        None => StdRng::from_entropy(),
+    };
+
+    match todo.random_pending(&mut rng) {
+        Some(value) => println!("{} : {}", value.id(), value.description()),
+        None => println!("There are no pending items to pick from!"),
+    }
+}
+
+///Action responsible to count the items of the TodoList
+pub fn count(todo: &TodoList) {
+    println!("{}", todo.summary_string())
+}
+
+/// Width of the progress bar of the status
+const STATUS_BAR_WIDTH: usize = 20;
+
+///Action responsible to summarize the whole list: the counts, the overdue items,
+///the next item to work on and how much is done
+pub fn status(todo: &TodoList, render: &Render) {
+    for line in status_lines(todo, render) {
+        println!("{}", line);
+    }
+}
+
+///The lines of the summary of the whole list, one per section
+fn status_lines(todo: &TodoList, render: &Render) -> Vec<String> {
+    let items = todo.items();
+    let done = items.iter().filter(|value| value.is_done()).count();
+    let next = match todo.next_task() {
+        Some(value) => format!("{} : {}", value.id(), value.description()),
+        None => String::from("nothing pending"),
+    };
+    vec![
+        format!("Items:    {}", todo.summary_string()),
+        format!("Overdue:  {}", todo.overdue(render.today).len()),
+        format!("Next:     {}", next),
+        format!(
+            "Progress: {}",
+            render.progress_bar(done, items.len(), STATUS_BAR_WIDTH)
+        ),
+    ]
+}
+
+///Action responsible to check the list for problems, printed as text or as json if `json`
+///Any problem is an error, so the program fails
+pub fn doctor(todo: &TodoList, json: bool, pretty: bool) -> Result<(), TodoError> {
+    let errors = todo.validate();
+    if json {
+        print_json(&errors, pretty);
+    } else if errors.is_empty() {
+        println!("No problems found, the list is valid!");
+    } else {
+        for error in &errors {
+            println!("{}", error);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(TodoError::Invalid(errors.len()))
+    }
+}
+
+///Action responsible to list the groups of items with the same description
+pub fn duplicates(todo: &TodoList) {
+    let groups = todo.find_duplicates();
+    if groups.is_empty() {
+        println!("There are no duplicated items!");
+    }
+    for (description, ids) in groups {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        println!("{} : {}", description, ids.join(", "));
+    }
+}
+
+///Action responsible to search the items whose description contains a term
+pub fn search(todo: &TodoList, term: &str, case_sensitive: bool, render: &Render) {
+    let items = todo.search(term, case_sensitive);
+    if items.is_empty() {
+        println!("There is no item matching: {} !", term);
+    } else {
+        render.table(&items);
+    }
+}
+
+///Action responsible to forecast when the pending items will be done
+pub fn forecast(todo: &TodoList) {
+    match (todo.avg_completion_time(), todo.forecast(Utc::now())) {
+        (Some(average), Some(date)) => {
+            println!(
+                "On average an item takes {} hour(s) to complete",
+                average.num_hours()
+            );
+            println!(
+                "All pending items may be done by {}",
+                date.format("%Y-%m-%d %H:%M")
+            );
+        }
+        _ => println!(
+            "Insufficient data: at least {} completed items are needed to forecast",
+            MIN_COMPLETED_FOR_AVERAGE
+        ),
+    }
+}
+
+///Action responsible to restore the nth most recent snapshot of the TodoList
+///Return if the list was restored
+pub fn rollback(todo: &mut TodoList, nth: &str) -> bool {
+    let nth: usize = match nth.trim().parse() {
+        Ok(nth) => nth,
+        Err(_) => {
+            println!("The given snapshot: {} is not a number!", nth);
+            return false;
+        }
+    };
+
+    match TodoList::read_snapshot(HISTORY_DIR, nth) {
+        Ok(snapshot) => {
+            *todo = snapshot;
+            println!("Todo list rolled back {} snapshot(s)!", nth);
+            true
+        }
+        Err(why) => {
+            println!("Could not roll back: {}", why);
+            false
+        }
+    }
+}
+
+///Action responsible to show the details of the items with the given ids
+///With `raw` each item is printed as json instead
+pub fn view(todo: &TodoList, items: &[String], raw: bool, pretty: bool, render: &Render) {
+    let mut ids = Vec::new();
+    for item in items {
+        match item.trim().parse::<u32>() {
+            Ok(id) => ids.push(id),
+            Err(_) => println!("The given id: {} is not a number!", item),
+        }
+    }
+
+    let found = todo.get_many_by_ids(&ids);
+    for id in ids
+        .iter()
+        .filter(|id| !found.iter().any(|value| value.id() == **id))
+    {
+        println!("There is no item with the given id: {} !", id);
+    }
+
+    if raw {
+        for value in found {
+            let json = if pretty {
+                value.to_json_pretty()
+            } else {
+                value.to_json()
+            };
+            println!("{}", json.unwrap_or("Nothing".to_string()))
+        }
+        return;
+    }
+
+    for value in found {
+        println!("#{} {}", value.id(), value.description());
+        println!("  Done:      {}", value.is_done());
+        if let Some(date) = value.created_at() {
+            println!("  Created:   {}", render.datetime(date));
+        }
+        if let Some(date) = value.completed_at() {
+            println!("  Completed: {}", render.datetime(date));
+        }
+        if let Some(note) = value.completion_note() {
+            println!("  Note:      {}", note);
+        }
+        if let Some(due) = value.due() {
+            println!("  Due:       {}", render.date(due));
+        }
+        println!("  Priority:  {}", value.priority());
+        if let Some(url) = value.url() {
+            println!("  Url:       {}", render.link(url, url));
+        }
+        if let Some(minutes) = value.actual_minutes() {
+            println!("  Spent:     {}h{:02}m", minutes / 60, minutes % 60);
+        }
+        if value.progress() > 0 {
+            println!("  Progress:  {}%", value.progress());
+        }
+        if let Some(owner) = value.owner() {
+            println!("  Owner:     {}", owner);
+        }
+        if !value.tags().is_empty() {
+            let tags: Vec<&str> = value.tags().iter().map(|tag| tag.as_str()).collect();
+            println!("  Tags:      {}", tags.join(", "));
+        }
+    }
+}
+
+///Action responsible to show the most recent events of an item
+pub fn history(todo: &TodoList, item: &str, limit: Option<String>) {
+    let id: u32 = match item.trim().parse() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("The given id: {} is not a number!", item);
+            return;
+        }
+    };
+    let limit = match limit.map(|limit| limit.trim().parse::<usize>()) {
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => {
+            println!("The given limit is not a number!");
+            return;
+        }
+        None => usize::MAX,
+    };
+
+    match todo.get_item_by_id(id) {
+        Some(value) => {
+            let history = value.history();
+            for event in history.iter().skip(history.len().saturating_sub(limit)) {
+                println!("{}  {}", event.at().format("%Y-%m-%d %H:%M"), event.event());
+            }
+        }
+        None => println!("There is no item with the given id: {} !", id),
+    }
+}
+
+///Action responsible to edit the whole TodoList in the user's editor
+///The list is only replaced if the edited file is a valid list
+///Return if the list was replaced
+///The file to edit is never one that already exists, as anyone could have put it there
+pub fn edit_all(todo: &mut TodoList) -> bool {
+    let path = std::env::temp_dir().join(format!("todo-edit-{}", std::process::id()));
+    let filename = path.to_string_lossy().to_string();
+    let json_path = format!("{}.json", filename);
+
+    let written = todo
+        .to_json_pretty()
+        .map_err(std::io::Error::other)
+        .and_then(|json| {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&json_path)?
+                .write_all(json.as_bytes())
+        });
+    if let Err(why) = written {
+        println!("Could not write the list to edit: {}", why);
+        return false;
+    }
+
+    let editor = std::env::var("EDITOR").unwrap_or("vi".to_string());
+    let mut words = editor.split_whitespace();
+    let status = std::process::Command::new(words.next().unwrap_or("vi"))
+        .args(words)
+        .arg(&json_path)
+        .status();
+
+    let edited = match status {
+        Ok(status) if status.success() => TodoList::read_json(&filename),
+        Ok(status) => Err(std::io::Error::other(format!(
+            "the editor exited with {}",
+            status
+        ))),
+        Err(why) => Err(why),
+    };
+    let _ = std::fs::remove_file(&json_path);
+
+    match edited {
+        Ok(edited) => {
+            // the checksum is stamped again when saving, so editing the items is fine
+            let problems: Vec<ValidationError> = edited
+                .validate()
+                .into_iter()
+                .filter(|problem| *problem != ValidationError::ChecksumMismatch)
+                .collect();
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                println!("The edited list is not valid, keeping the original one!");
+                return false;
+            }
+            *todo = edited;
+            println!("Todo list replaced by the edited one!");
+            true
+        }
+        Err(why) => {
+            println!("Keeping the original list, the edit failed: {}", why);
+            false
+        }
+    }
+}
+
+///The markers given by the flag, or else by the config
+fn markers(flag: Option<String>, config: &Config) -> Markers {
+    match flag.as_ref().or(config.markers.as_ref()) {
+        Some(markers) => Markers::parse(markers).unwrap_or_else(|why| {
+            eprintln!("{}, using the default ones", why);
+            Markers::default()
+        }),
+        None => Markers::default(),
+    }
+}
+
+///If the output is colored, by the mode given by the flag, or else by the config
+fn color(flag: Option<String>, config: &Config) -> bool {
+    let mode = match flag.as_ref().or(config.color.as_ref()) {
+        Some(mode) => ColorMode::parse(mode).unwrap_or_else(|why| {
+            eprintln!("{}, using auto", why);
+            ColorMode::Auto
+        }),
+        None => ColorMode::Auto,
+    };
+    mode.enabled()
+}
+
+///The known action closest to the given one, if it is close enough
+fn closest_action(action: &str) -> Option<String> {
+    ACTIONS
+        .iter()
+        .map(|known| (strsim::levenshtein(action, known), known))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| String::from(*known))
+}
+
+///Run the action given by the arguments on the TodoList saved in the file
+pub fn render_cli(mut args: Args, filename: &str, config: &Config) -> Result<(), TodoError> {
+    // a list that can't be parsed is the first problem the doctor must report
+    let strict_parse = args.flag("--strict-parse") || args.nth(0) == Some("doctor");
+    let create = !args.flag("--no-create");
+    if args.nth(0) == Some("interactive") {
+        return interactive(args, filename, config, strict_parse, create);
+    }
+
+    let mut todo = read_configured(filename, config, strict_parse, create)?;
+    let archived = auto_archive(&mut todo, config, Utc::now());
+    let result = run_action(&mut todo, args, filename, config);
+    // the archived items are already in the archive, so they must leave the list even on errors
+    if archived || matches!(result, Ok(true)) {
+        save(&mut todo, filename)
+    }
+    result.map(|_| ())
+}
+
+///Read the TodoList and apply the config to it
+fn read_configured(
+    filename: &str,
+    config: &Config,
+    strict: bool,
+    create: bool,
+) -> Result<TodoList, TodoError> {
+    let mut todo = read(filename, strict, create)?;
+    if let Some(max_history) = config.max_history {
+        todo.set_max_history(max_history);
+    }
+    if let Some(prune) = config.prune_empty_on_save {
+        todo.set_prune_empty_on_save(prune);
+    }
+    Ok(todo)
+}
+
+///Action responsible to run several actions, one per line of the input, on the same TodoList
+///The list is saved after every `--flush-every` changes, and when the input ends
+pub fn interactive(
+    mut args: Args,
+    filename: &str,
+    config: &Config,
+    strict: bool,
+    create: bool,
+) -> Result<(), TodoError> {
+    let flush_every = match args.option("--flush-every") {
+        Some(value) => value
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| TodoError::InvalidValue(String::from("--flush-every"), value))?,
+        None => 0,
+    };
+
+    let mut todo = read_configured(filename, config, strict, create)?;
+    let prompt = std::io::stdin().is_terminal();
+    let unsaved = usize::from(auto_archive(&mut todo, config, Utc::now()));
+    let mut stdin = std::io::stdin().lock().lines();
+    let lines = std::iter::from_fn(|| {
+        if prompt {
+            print!("> ");
+            let _ = std::io::stdout().flush();
+        }
+        stdin.next()?.ok()
+    });
+
+    session(
+        &mut todo,
+        lines,
+        filename,
+        config,
+        flush_every,
+        unsaved,
+        HISTORY_DIR,
+    );
+    Ok(())
+}
+
+///Run the action of each line on the TodoList, until the lines end or one is `exit`
+///The list is saved, with its snapshots in `history`, after every `flush_every` changes
+///counting the `unsaved` ones, and at the end
+fn session(
+    todo: &mut TodoList,
+    lines: impl Iterator<Item = String>,
+    filename: &str,
+    config: &Config,
+    flush_every: usize,
+    mut unsaved: usize,
+    history: &str,
+) {
+    for line in lines {
+        let args = Args::parse_line(&line);
+        match args.nth(0) {
+            None => continue,
+            Some("exit") | Some("quit") => break,
+            Some("interactive") => {
+                println!("Already in interactive mode!");
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        match run_action(todo, args, filename, config) {
+            Ok(true) => unsaved += 1,
+            Ok(false) => {}
+            Err(why) => println!("{}", why),
+        }
+        if flush_every > 0 && unsaved >= flush_every {
+            save_in(todo, filename, history);
+            unsaved = 0;
+        }
+    }
+
+    if unsaved > 0 {
+        save_in(todo, filename, history)
+    }
+}
+
+///Run the action given by the arguments on the TodoList, read from the file
+///Return if the list changed and needs to be saved
+fn run_action(
+    todo: &mut TodoList,
+    mut args: Args,
+    filename: &str,
+    config: &Config,
+) -> Result<bool, TodoError> {
+    // flags that shape the json output of any action
+    let json_array = args.flag("--json-array");
+    let compact_json = args.flag("--compact") || json_array;
+    let pretty = args.flag("--pretty") || !compact_json;
+    let explicit_format = args.option("--format");
+    let format = match &explicit_format {
+        Some(format) => format.clone(),
+        None if json_array => String::from("json"),
+        None => String::from("text"),
+    };
+    // flags of specific actions
+    let case_sensitive = args.flag("--case-sensitive");
+    let mine = args.flag("--mine");
+    let mut query = Query {
+        done: if args.flag("--pending") {
+            Some(false)
+        } else if args.flag("--done") {
+            Some(true)
+        } else {
+            None
+        },
+        owner: None,
+        tag: args.option("--tag"),
+        since_id: match args.option("--since-id") {
+            Some(id) => Some(
+                id.trim()
+                    .parse()
+                    .map_err(|_| TodoError::InvalidValue(String::from("--since-id"), id))?,
+            ),
+            None => None,
+        },
+    };
+    let count_only = args.flag("--count-only");
+    let at_top = args.flag("--at-top");
+    let owner = args.option("--owner");
+    // highlighted by default, `--highlight-overdue` only makes it explicit
+    let highlight_overdue = args.flag("--highlight-overdue");
+    let no_highlight_overdue = args.flag("--no-highlight-overdue");
+    let render = Render {
+        markers: markers(args.option("--markers"), config),
+        relative_dates: args.flag("--relative-dates"),
+        header: !args.flag("--no-header"),
+        color: color(args.option("--color"), config),
+        highlight_overdue: highlight_overdue || !no_highlight_overdue,
+        today: Utc::now().date_naive(),
+    };
+    let due_date = args.option("--due");
+    let limit = args.option("--limit");
+    let no_ids = args.flag("--no-ids");
+    let weeks = args.option("--weeks");
+    let completed = args.flag("--completed");
+    let down = args.flag("--down");
+    let priority = args.option("--priority");
+    let note = args.option("--note");
+    let seed = args.option("--seed");
+    let dry_run = DryRun::parse(args.flag("--dry-run"), args.option("--dry-run-format"))?;
+    let raw = args.flag("--raw");
+    let strategy = args.option("--strategy");
+    let contains = args.option("--contains");
+    let yes = args.flag("--yes");
+    let anonymize = args.flag("--anonymize");
+    let cloud = args.flag("--cloud");
+    let reverse = args.flag("--reverse");
+    let json = args.flag("--json");
+    let spent = args.option("--spent");
+    let new_tags = args.option("--tags");
+    let with_computed = args.flag("--with-computed");
+    let url = args.option("--url");
+    let scrub_tags = args.flag("--scrub-tags");
+    let scrub_owners = args.flag("--scrub-owners");
+
+    let action = String::from(args.nth(0).ok_or(TodoError::MissingAction)?);
+    if !ACTIONS.contains(&action.as_str()) {
+        let suggestion = closest_action(&action);
+        return Err(TodoError::InvalidAction(action, suggestion));
+    }
+    let mut item = "".to_string();
+    // actions that only need the action itself
+    let actions_only_action = [
+        "help",
+        "show",
+        "list",
+        "forecast",
+        "rollback",
+        "edit-all",
+        "export-json",
+        "export-md",
+        "timeline",
+        "histogram",
+        "clear",
+        "tags",
+        "compact",
+        "count",
+        "random",
+        "next",
+        "status",
+        "duplicates",
+        "doctor",
+    ];
+    // removing the completed items or the ones matching a filter doesn't need an item either,
+    // neither does completing the ones matching a search
+    if action == "remove" && completed {
+        query.done = Some(true);
+    }
+    let needs_item = !(actions_only_action.contains(&action.as_str())
+        || action == "remove" && !query.is_empty()
+        || action == "complete" && contains.is_some());
+
+    if needs_item {
+        item = String::from(args.nth(1).ok_or(TodoError::MissingItem)?);
+    }
+    // println!("{:?}, {:?}", action, item);
+
+    let mut changes = true;
+
+    if action == "add" {
+        let options = AddOptions {
+            owner,
+            at_top,
+            due: due_date,
+            priority,
+            // `--done` was already taken as a filter, but when adding it marks the items as done
+            done: query.done == Some(true),
+            tags: new_tags.as_deref().map(parse_tags).unwrap_or_default(),
+            url,
+            archive: config.archive_on_complete.clone(),
+        };
+        add(todo, &args.positional()[1..], options);
+    } else if action == "remove" && !query.is_empty() {
+        changes = remove_matching(todo, &query, dry_run, json, pretty, &render);
+    } else if action == "clear" {
+        clear(todo);
+    } else if action == "remove" {
+        remove(todo, String::from(&item), json, pretty);
+    } else if action == "update" {
+        update(
+            todo,
+            String::from(&item),
+            config.archive_on_complete.as_deref(),
+        );
+    } else if action == "complete" && contains.is_some() {
+        changes = complete_matching(
+            todo,
+            &contains.unwrap_or_default(),
+            case_sensitive,
+            yes,
+            note,
+            config.archive_on_complete.as_deref(),
+        );
+    } else if action == "complete" {
+        changes = complete(
+            todo,
+            &item,
+            note,
+            spent,
+            config.archive_on_complete.as_deref(),
+        );
+    } else if action == "reopen" {
+        changes = reopen(todo, &item);
+    } else if action == "help" {
+        changes = false;
+        help()
+    } else if action == "show" {
+        changes = false;
+        let format = explicit_format.or(json_array.then(|| String::from("json")));
+        show(todo, format.as_deref(), pretty, &render)
+    } else if action == "list" {
+        changes = false;
+        if mine {
+            query.owner = Some(current_user().ok_or(TodoError::UnknownUser)?);
+        }
+        list(
+            todo,
+            &query,
+            &format,
+            pretty,
+            count_only,
+            with_computed,
+            &render,
+        )
+    } else if action == "archive-to" {
+        changes = archive_to(todo, filename, &item);
+    } else if action == "archive-old" {
+        let days = match item.trim().parse::<i64>() {
+            Ok(days) if days >= 0 => days,
+            _ => return Err(TodoError::InvalidValue(String::from("archive-old"), item)),
+        };
+        let archived = archive_old(todo, days, config, Utc::now());
+        println!(
+            "{} completed todo item(s) archived in {}.csv!",
+            archived,
+            archive_file(config)
+        );
+        changes = archived > 0;
+    } else if action == "assign" {
+        assign(todo, item, args.nth(2).map(String::from));
+    } else if action == "export-json" {
+        changes = false;
+        export_json(todo, anonymize, scrub_tags, scrub_owners)
+    } else if action == "export-md" {
+        changes = false;
+        export_markdown(todo, !no_ids)
+    } else if action == "edit-all" {
+        changes = edit_all(todo);
+    } else if action == "compact" {
+        changes = compact(todo, dry_run, pretty);
+    } else if action == "due" {
+        changes = due(todo, &item, args.nth(2));
+    } else if action == "progress" {
+        changes = progress(
+            todo,
+            &item,
+            args.nth(2),
+            config.archive_on_complete.as_deref(),
+        );
+    } else if action == "bump" {
+        changes = bump(todo, &item, down);
+    } else if action == "max-items" {
+        changes = max_items(todo, &item);
+    } else if action == "tag" && item == "rename" {
+        changes = rename_tag(todo, args.nth(2), args.nth(3));
+    } else if action == "tag" {
+        changes = tag(todo, item, args.nth(2));
+    } else if action == "tags" {
+        changes = false;
+        tags(todo, cloud)
+    } else if action == "view" {
+        changes = false;
+        view(todo, &args.positional()[1..], raw, pretty, &render)
+    } else if action == "history" {
+        changes = false;
+        history(todo, &item, limit)
+    } else if action == "import" {
+        changes = import(todo, &item, reverse);
+    } else if action == "import-list" {
+        changes = import_list(todo, &item);
+    } else if action == "merge" {
+        changes = merge(todo, &item, strategy);
+    } else if action == "diff" {
+        changes = false;
+        diff(&item, args.nth(2), &format, pretty)
+    } else if action == "next" {
+        changes = false;
+        next(todo)
+    } else if action == "random" {
+        changes = false;
+        random(todo, seed)
+    } else if action == "doctor" {
+        changes = false;
+        doctor(todo, json, pretty)?
+    } else if action == "duplicates" {
+        changes = false;
+        duplicates(todo)
+    } else if action == "status" {
+        changes = false;
+        status(todo, &render)
+    } else if action == "count" {
+        changes = false;
+        count(todo)
+    } else if action == "search" {
+        changes = false;
+        search(todo, &item, case_sensitive, &render)
+    } else if action == "rollback" {
+        changes = rollback(todo, args.nth(1).unwrap_or("1"));
+    } else if action == "histogram" {
+        changes = false;
+        histogram(todo)
+    } else if action == "timeline" {
+        changes = false;
+        timeline(todo, weeks)
+    } else if action == "forecast" {
+        changes = false;
+        forecast(todo)
+    } else {
+        return Err(TodoError::InvalidAction(action, None));
+    }
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_of(descriptions: &[&str]) -> TodoList {
+        let mut todo = TodoList::build();
+        for description in descriptions {
+            todo.insert(String::from(*description));
+        }
+        todo
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| String::from(*value)).collect()
+    }
+
+    /// Run the action of a line, the way the interactive mode does, without saving
+    fn run(todo: &mut TodoList, line: &str) -> Result<bool, TodoError> {
+        run_action(todo, Args::parse_line(line), "unused", &Config::default())
+    }
+
+    fn render() -> Render {
+        Render {
+            markers: Markers::default(),
+            relative_dates: false,
+            header: true,
+            color: false,
+            highlight_overdue: true,
+            today: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+        }
+    }
+
+    #[test]
+    fn compact_json_is_a_single_line() {
+        let todo = list_of(&["a", "b"]);
+        assert_eq!(json_string(&todo.items(), false).lines().count(), 1);
+        assert!(json_string(&todo.items(), true).lines().count() > 1);
+    }
+
+    #[test]
+    fn current_user_from_the_environment() {
+        std::env::set_var("TODO_USER", "ana");
+        std::env::set_var("USER", "rui");
+        assert_eq!(current_user().as_deref(), Some("ana"));
+        std::env::set_var("TODO_USER", " ");
+        assert_eq!(current_user().as_deref(), Some("rui"));
+
+        std::env::remove_var("TODO_USER");
+        std::env::remove_var("USER");
+        assert_eq!(current_user(), None);
+        let args = Args::build(strings(&["list", "--mine"]));
+        let filename = temp_file("mine", "list");
+        assert_eq!(
+            render_cli(args, &filename, &Config::default()),
+            Err(TodoError::UnknownUser)
+        );
+    }
+
+    #[test]
+    fn markers_of_the_flag_before_the_config() {
+        let config: Config = toml::from_str("markers = \"✔/✗\"").unwrap();
+        assert_eq!(markers(None, &config).of(true), "[✔]");
+        assert_eq!(markers(Some(String::from("+/-")), &config).of(true), "[+]");
+        assert_eq!(markers(Some(String::from("bad")), &config).of(true), "[x]");
+    }
+
+    #[test]
+    fn add_at_the_top() {
+        let mut todo = list_of(&["x"]);
+        let options = AddOptions {
+            at_top: true,
+            ..AddOptions::default()
+        };
+        add(&mut todo, &strings(&["a"]), options);
+        add(&mut todo, &strings(&["b"]), AddOptions::default());
+        let order: Vec<&str> = todo.items().iter().map(|item| item.description()).collect();
+        assert_eq!(order, vec!["a", "x", "b"]);
+    }
+
+    #[test]
+    fn edit_all_in_a_scripted_editor() {
+        let mut todo = list_of(&["first", "other"]);
+        std::env::set_var("EDITOR", "sed -i s/first/second/");
+        assert!(edit_all(&mut todo));
+        assert_eq!(todo.get_item_by_id(0).unwrap().description(), "second");
+
+        std::env::set_var("EDITOR", "sed -i s/\"id\":.1,/\"id\":0,/");
+        assert!(!edit_all(&mut todo));
+        assert_eq!(todo.get_item_by_id(1).unwrap().description(), "other");
+
+        // an empty description is not a valid list
+        std::env::set_var("EDITOR", "sed -i s/second//");
+        assert!(!edit_all(&mut todo));
+        assert_eq!(todo.get_item_by_id(0).unwrap().description(), "second");
+
+        std::env::set_var("EDITOR", "false");
+        assert!(!edit_all(&mut todo));
+        assert_eq!(todo.get_item_by_id(0).unwrap().description(), "second");
+
+        // a file already in the place of the one to edit is left alone
+        let json_path = std::env::temp_dir().join(format!("todo-edit-{}.json", std::process::id()));
+        std::fs::write(&json_path, "planted").unwrap();
+        std::env::set_var("EDITOR", "sed -i s/second/third/");
+        assert!(!edit_all(&mut todo));
+        assert_eq!(todo.get_item_by_id(0).unwrap().description(), "second");
+        assert_eq!(std::fs::read_to_string(&json_path).unwrap(), "planted");
+        std::fs::remove_file(&json_path).unwrap();
+    }
+
+    #[test]
+    fn clear_the_completed() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.update_todo_item_id(1);
+        clear(&mut todo);
+        let left: Vec<&str> = todo.items().iter().map(|item| item.description()).collect();
+        assert_eq!(left, vec!["a"]);
+    }
+
+    /// An empty directory of its own for each test, as they run in parallel
+    fn temp_file(test: &str, name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("todo-cli-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn lenient_read_of_a_malformed_list() {
+        let filename = temp_file("lenient", "list");
+        std::fs::write(format!("{}.json", filename), "{ not json").unwrap();
+        assert!(read(&filename, false, true).unwrap().items().is_empty());
+    }
+
+    #[test]
+    fn max_items_evicts_at_once() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        assert!(max_items(&mut todo, "2"));
+        assert_eq!(todo.items().len(), 2);
+        assert!(!max_items(&mut todo, "0"));
+        assert!(max_items(&mut todo, "off"));
+        assert_eq!(todo.max_items(), None);
+    }
+
+    #[test]
+    fn update_archived_in_a_csv() {
+        let archive = temp_file("archive", "done");
+        let mut todo = list_of(&["a", "b"]);
+        update(&mut todo, String::from("b"), Some(&archive));
+        update(&mut todo, String::from("0"), Some(&archive));
+        // reopening is not archived
+        update(&mut todo, String::from("1"), Some(&archive));
+        let csv = std::fs::read_to_string(format!("{}.csv", archive)).unwrap();
+        assert_eq!(csv, "Id,Description,Done\n1,b,true\n0,a,true\n");
+    }
+
+    #[test]
+    fn add_several_with_a_duplicate() {
+        let mut todo = list_of(&["b"]);
+        add(&mut todo, &strings(&["a", "b", "c"]), AddOptions::default());
+        let order: Vec<&str> = todo.items().iter().map(|item| item.description()).collect();
+        assert_eq!(order, vec!["b", "a", "c"]);
+        assert_eq!(
+            todo.get_item_by_description(String::from("c"))
+                .unwrap()
+                .id(),
+            2
+        );
+    }
+
+    #[test]
+    fn add_with_a_due_date() {
+        let mut todo = TodoList::build();
+        let options = AddOptions {
+            due: Some(String::from("2024-01-12")),
+            ..AddOptions::default()
+        };
+        add(&mut todo, &strings(&["a"]), options);
+        assert_eq!(
+            todo.get_item_by_id(0).unwrap().due(),
+            NaiveDate::from_ymd_opt(2024, 1, 12)
+        );
+        let options = AddOptions {
+            due: Some(String::from("12/01/2024")),
+            ..AddOptions::default()
+        };
+        add(&mut todo, &strings(&["b"]), options);
+        assert_eq!(todo.items().len(), 1);
+    }
+
+    #[test]
+    fn errors_instead_of_exiting() {
+        let filename = temp_file("errors", "list");
+        let cli = |values: &[&str]| {
+            render_cli(Args::build(strings(values)), &filename, &Config::default())
+        };
+        assert!(matches!(cli(&[]), Err(TodoError::MissingAction)));
+        assert!(matches!(cli(&["add"]), Err(TodoError::MissingItem)));
+        assert!(matches!(
+            cli(&["frobnicate", "x"]),
+            Err(TodoError::InvalidAction(..))
+        ));
+        assert!(!std::path::Path::new(&format!("{}.json", filename)).exists());
+
+        std::fs::write(format!("{}.json", filename), "{ not json").unwrap();
+        assert!(matches!(
+            read(&filename, true, true),
+            Err(TodoError::Parse(_))
+        ));
+        assert!(matches!(
+            cli(&["show", "--strict-parse"]),
+            Err(TodoError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn count_only_of_a_filtered_list() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.update_todo_item_id(1);
+        let pending = Query {
+            done: Some(false),
+            ..Query::default()
+        };
+        assert_eq!(count_line(&todo, &pending), "2");
+        assert_eq!(count_line(&todo, &Query::default()), "3");
+    }
+
+    #[test]
+    fn unknown_action_suggests_the_closest() {
+        assert_eq!(closest_action("ad").as_deref(), Some("add"));
+        assert_eq!(closest_action("lsit").as_deref(), Some("list"));
+        assert_eq!(closest_action("frobnicate"), None);
+
+        let filename = temp_file("suggest", "list");
+        let args = Args::build(strings(&["ad", "milk"]));
+        let why = render_cli(args, &filename, &Config::default()).unwrap_err();
+        assert_eq!(
+            why,
+            TodoError::InvalidAction(String::from("ad"), Some(String::from("add")))
+        );
+        assert_eq!(why.to_string(), "Unknown command 'ad'. Did you mean 'add'?");
+        assert_eq!(run(&mut TodoList::build(), "help"), Ok(false));
+    }
+
+    #[test]
+    fn bump_stops_at_the_limits() {
+        let mut todo = list_of(&["a"]);
+        assert!(bump(&mut todo, "0", false));
+        assert!(!bump(&mut todo, "0", false));
+        assert_eq!(todo.get_item_by_id(0).unwrap().priority(), Priority::High);
+        assert!(bump(&mut todo, "0", true));
+        assert_eq!(todo.get_item_by_id(0).unwrap().priority(), Priority::Medium);
+        assert!(!bump(&mut todo, "9", true));
+    }
+
+    #[test]
+    fn session_flushed_after_every_change() {
+        let filename = temp_file("flush", "list");
+        let history = temp_file("flush-history", "history");
+        let saved = |filename: &str| {
+            TodoList::read_json(filename)
+                .map(|todo| todo.items().len())
+                .ok()
+        };
+        // each line is read once the previous one ran, so the file is checked mid-session
+        let lines = strings(&["add a", "list", "add b", "exit", "add c"])
+            .into_iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let expected = [None, Some(1), Some(1), Some(2), Some(2)][index];
+                assert_eq!(saved(&filename), expected, "before {}", line);
+                line
+            });
+
+        let mut todo = TodoList::build();
+        session(
+            &mut todo,
+            lines,
+            &filename,
+            &Config::default(),
+            1,
+            0,
+            &history,
+        );
+        assert_eq!(saved(&filename), Some(2));
+        assert_eq!(TodoList::snapshots(&history).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn session_saved_only_at_the_end() {
+        let filename = temp_file("no-flush", "list");
+        let history = temp_file("no-flush-history", "history");
+        let lines = strings(&["add a", "add b"]).into_iter().inspect(|_| {
+            assert!(TodoList::read_json(&filename).is_err());
+        });
+
+        let mut todo = TodoList::build();
+        session(
+            &mut todo,
+            lines,
+            &filename,
+            &Config::default(),
+            0,
+            0,
+            &history,
+        );
+        assert_eq!(TodoList::read_json(&filename).unwrap().items().len(), 2);
+    }
+
+    #[test]
+    fn dry_run_of_a_filtered_remove() {
+        let filename = temp_file("dry-run", "list");
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.tag(0, String::from("x"));
+        todo.tag(2, String::from("x"));
+        todo.save_json(&filename).unwrap();
+        let path = format!("{}.json", filename);
+        let before = std::fs::read(&path).unwrap();
+
+        let args = Args::build(strings(&["remove", "--tag", "x", "--dry-run"]));
+        render_cli(args, &filename, &Config::default()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+
+        let query = Query {
+            tag: Some(String::from("x")),
+            ..Query::default()
+        };
+        let render = Render {
+            markers: Markers::default(),
+            relative_dates: false,
+            header: false,
+            color: false,
+            highlight_overdue: true,
+            today: Utc::now().date_naive(),
+        };
+        let lines = dry_run_lines(&todo, &query, &render);
+        let listed: Vec<&str> = lines[..2]
+            .iter()
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(listed, vec!["0", "2"]);
+        assert_eq!(lines[2], "2 todo item(s) would be deleted!");
+    }
+
+    #[test]
+    fn add_already_done() {
+        let mut todo = TodoList::build();
+        assert_eq!(run(&mut todo, "add a --done"), Ok(true));
+        assert!(todo.get_item_by_id(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn add_done_to_a_full_list_keeps_the_new_item() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.set_max_items(Some(2));
+        assert_eq!(run(&mut todo, "add c --done"), Ok(true));
+        assert_eq!(todo.descriptions(), vec!["b", "c"]);
+        assert!(todo.get_item_by_id(2).unwrap().is_done());
+    }
+
+    #[test]
+    fn completed_by_adding_or_progress_are_archived() {
+        let archive = temp_file("archive-progress", "done");
+        let mut todo = TodoList::build();
+        let options = AddOptions {
+            done: true,
+            archive: Some(archive.clone()),
+            ..AddOptions::default()
+        };
+        add(&mut todo, &strings(&["a"]), options);
+        add(&mut todo, &strings(&["b"]), AddOptions::default());
+        assert!(progress(&mut todo, "1", Some("60"), Some(&archive)));
+        assert!(progress(&mut todo, "1", Some("+40"), Some(&archive)));
+        // already done, so it is not archived twice
+        assert!(progress(&mut todo, "1", Some("100"), Some(&archive)));
+
+        let csv = std::fs::read_to_string(format!("{}.csv", archive)).unwrap();
+        let archived: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(archived, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn since_id_must_be_a_number() {
+        let mut todo = list_of(&["a"]);
+        assert_eq!(run(&mut todo, "list --since-id 0 --json-array"), Ok(false));
+        assert!(matches!(
+            run(&mut todo, "list --since-id first"),
+            Err(TodoError::InvalidValue(..))
+        ));
+    }
+
+    #[test]
+    fn complete_matching_needs_yes_for_many() {
+        let mut todo = list_of(&[
+            "fix 1", "fix 2", "fix 3", "fix 4", "fix 5", "fix 6", "other",
+        ]);
+        assert!(!complete_matching(
+            &mut todo, "fix", false, false, None, None
+        ));
+        assert!(todo.items().iter().all(|item| !item.is_done()));
+
+        assert!(complete_matching(&mut todo, "fix", false, true, None, None));
+        let done: Vec<u32> = todo
+            .items()
+            .iter()
+            .filter(|item| item.is_done())
+            .map(|item| item.id())
+            .collect();
+        assert_eq!(done, vec![0, 1, 2, 3, 4, 5]);
+        assert!(!complete_matching(&mut todo, "FIX", true, true, None, None));
+    }
+
+    #[test]
+    fn format_by_the_output() {
+        assert_eq!(default_format(true), "text");
+        assert_eq!(default_format(false), "json");
+    }
+
+    #[test]
+    fn status_of_the_list() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.complete_by_id(0, None);
+        todo.set_due(1, NaiveDate::from_ymd_opt(2024, 1, 9));
+        assert_eq!(
+            status_lines(&todo, &render()),
+            vec![
+                "Items:    2 items, 1 done, 1 pending",
+                "Overdue:  1",
+                "Next:     1 : b",
+                "Progress: [##########----------] 50%",
+            ]
+        );
+        assert_eq!(
+            status_lines(&TodoList::build(), &render())[2],
+            "Next:     nothing pending"
+        );
+    }
+
+    #[test]
+    fn remove_as_json() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        let removed: serde_json::Value =
+            serde_json::from_str(&removal(&mut todo, "0", true, false)).unwrap();
+        assert_eq!(removed[0]["id"], 0);
+        assert_eq!(removed[0]["description"], "a");
+        assert_eq!(todo.descriptions(), vec!["b", "c"]);
+        assert_eq!(
+            removal(&mut todo, "a", true, false),
+            "There is no item with the given description: a !"
+        );
+        assert_eq!(run(&mut todo, "remove 1 --json"), Ok(true));
+        assert_eq!(todo.descriptions(), vec!["c"]);
+    }
+
+    #[test]
+    fn auto_archive_of_the_old_completed() {
+        let archive = temp_file("auto-archive", "old");
+        let mut config = Config {
+            archive_on_complete: Some(archive.clone()),
+            auto_archive_days: Some(5),
+            ..Config::default()
+        };
+        let mut todo = list_of(&["a", "b"]);
+        todo.complete_by_id(0, None);
+
+        assert!(!auto_archive(&mut todo, &config, Utc::now()));
+        assert_eq!(todo.len(), 2);
+
+        config.auto_archive_days = Some(-1);
+        assert!(!auto_archive(
+            &mut todo,
+            &config,
+            Utc::now() + Duration::days(10)
+        ));
+        assert_eq!(todo.len(), 2);
+
+        config.auto_archive_days = Some(5);
+        assert!(auto_archive(
+            &mut todo,
+            &config,
+            Utc::now() + Duration::days(10)
+        ));
+        assert_eq!(todo.descriptions(), vec!["b"]);
+        let csv = std::fs::read_to_string(format!("{}.csv", archive)).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("0,a,true"));
+    }
+
+    #[test]
+    fn dry_runs_change_nothing() {
+        let mut todo = list_of(&["a", " A", "b"]);
+        todo.complete_by_id(2, None);
+        let before = todo.to_json().unwrap();
+        assert_eq!(run(&mut todo, "remove --completed --dry-run"), Ok(false));
+        assert_eq!(run(&mut todo, "compact --dry-run-format json"), Ok(false));
+        assert_eq!(todo.to_json().unwrap(), before);
+        assert!(matches!(
+            run(&mut todo, "compact --dry-run-format yaml"),
+            Err(TodoError::InvalidValue(..))
+        ));
+    }
+
+    #[test]
+    fn remove_preview_as_json() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.complete_by_id(1, None);
+        let query = Query {
+            done: Some(true),
+            ..Query::default()
+        };
+        let preview: serde_json::Value =
+            serde_json::from_str(&json_string(&remove_preview(&todo, &query), false)).unwrap();
+        assert_eq!(preview["action"], "remove");
+        assert_eq!(preview["count"], 1);
+        assert_eq!(preview["items"][0]["id"], 1);
+    }
+
+    #[test]
+    fn complete_with_the_time_spent() {
+        assert_eq!(parse_minutes("45m"), Ok(45));
+        assert_eq!(parse_minutes("2h"), Ok(120));
+        assert_eq!(parse_minutes("1h30m"), Ok(90));
+        for invalid in ["", "30", "h", "1x", "99999999999h"] {
+            assert!(parse_minutes(invalid).is_err(), "{}", invalid);
+        }
+
+        let mut todo = list_of(&["a"]);
+        assert_eq!(run(&mut todo, "complete 0 --spent 1h30m"), Ok(true));
+        assert_eq!(todo.get_item_by_id(0).unwrap().actual_minutes(), Some(90));
+        let mut todo = list_of(&["a"]);
+        assert_eq!(run(&mut todo, "complete 0 --spent soon"), Ok(false));
+        assert!(!todo.get_item_by_id(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn doctor_fails_on_problems() {
+        let broken: TodoList = serde_json::from_str(
+            r#"{"list":{"":{"id":0,"description":"","done":false}},"next_id":1}"#,
+        )
+        .unwrap();
+        assert_eq!(doctor(&broken, true, false), Err(TodoError::Invalid(1)));
+        assert_eq!(doctor(&list_of(&["a"]), false, false), Ok(()));
+    }
+
+    #[test]
+    fn add_with_multi_word_tags() {
+        assert_eq!(
+            parse_tags("urgent, high prio,, "),
+            vec!["urgent", "high prio"]
+        );
+        assert!(parse_tags(" , ").is_empty());
+
+        let mut todo = TodoList::build();
+        run(&mut todo, "add a --tags \"urgent, high prio\"").unwrap();
+        let tags: Vec<&str> = todo
+            .get_item_by_id(0)
+            .unwrap()
+            .tags()
+            .iter()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(tags, vec!["high prio", "urgent"]);
+    }
+
+    #[test]
+    fn json_with_the_computed_fields() {
+        let mut todo = list_of(&["a"]);
+        todo.set_due(0, NaiveDate::from_ymd_opt(2024, 1, 9));
+        let item = todo.get_item_by_id(0).unwrap();
+        let computed = WithComputed {
+            item,
+            overdue: item.is_overdue(render().today),
+        };
+        let json: serde_json::Value = serde_json::from_str(&json_string(&computed, false)).unwrap();
+        assert_eq!(json["description"], "a");
+        assert_eq!(json["overdue"], true);
+    }
+
+    #[test]
+    fn archive_the_completed_in_another_list() {
+        let filename = temp_file("archive-to", "list");
+        let target = temp_file("archive-to-target", "done");
+        let mut other = list_of(&["old"]);
+        other.complete_by_id(0, None);
+        other.set_max_items(Some(1));
+        other.save_json(&target).unwrap();
+
+        let mut todo = list_of(&["a", "b"]);
+        todo.complete_by_id(0, None);
+        assert!(!archive_to(&mut todo, &filename, &filename));
+        assert!(archive_to(
+            &mut todo,
+            &filename,
+            &format!("{}.json", target)
+        ));
+        assert_eq!(todo.descriptions(), vec!["b"]);
+        assert_eq!(
+            TodoList::read_json(&target).unwrap().descriptions(),
+            vec!["a"]
+        );
+        assert!(!archive_to(&mut todo, &filename, &target));
+    }
+
+    #[test]
+    fn archive_to_an_unsaved_target_keeps_the_items() {
+        let filename = temp_file("archive-to-unsaved", "list");
+        let target = format!("{}/done", temp_file("archive-to-unsaved-target", "missing"));
+        let mut todo = list_of(&["a", "b"]);
+        todo.complete_by_id(0, None);
+
+        assert!(!archive_to(&mut todo, &filename, &target));
+        assert_eq!(todo.descriptions(), vec!["a", "b"]);
+        assert!(todo.get_item_by_id(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn missing_list_without_creating_it() {
+        let filename = temp_file("no-create", "list");
+        let args = Args::build(strings(&["list", "--no-create"]));
+        assert_eq!(
+            render_cli(args, &filename, &Config::default()),
+            Err(TodoError::NotFound(filename.clone()))
+        );
+        assert!(read(&filename, false, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn urls_must_be_http() {
+        assert_eq!(
+            parse_url(" https://example.com/pull/1 ").as_deref(),
+            Ok("https://example.com/pull/1")
+        );
+        assert!(parse_url("http://localhost:8080").is_ok());
+        for invalid in [
+            "example.com",
+            "ftp://example.com",
+            "https://",
+            "https:///path",
+            "https://a b",
+        ] {
+            assert!(parse_url(invalid).is_err(), "{}", invalid);
+        }
+
+        let mut todo = TodoList::build();
+        run(&mut todo, "add review --url https://example.com/pull/1").unwrap();
+        assert_eq!(
+            todo.get_item_by_id(0).unwrap().url(),
+            Some("https://example.com/pull/1")
+        );
+    }
+
+    #[test]
+    fn import_list_leaves_the_other_file_alone() {
+        let other = temp_file("import-list", "other");
+        list_of(&["a", "b"]).save_json(&other).unwrap();
+        let path = format!("{}.json", other);
+        let before = std::fs::read(&path).unwrap();
+
+        let mut todo = list_of(&["b"]);
+        assert!(import_list(&mut todo, &path));
+        assert_eq!(todo.descriptions(), vec!["b", "a"]);
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert!(!import_list(&mut todo, &path));
+    }
+
+    #[test]
+    fn merges_keep_the_list_within_its_maximum() {
+        let other = temp_file("merge-full", "other");
+        list_of(&["c", "d"]).save_json(&other).unwrap();
+        let csv = temp_file("import-full", "items");
+        std::fs::write(
+            format!("{}.csv", csv),
+            "Id,Description,Done\n0,e,false\n1,f,false\n",
+        )
+        .unwrap();
+
+        let mut todo = list_of(&["a", "b"]);
+        todo.set_max_items(Some(3));
+        assert!(import_list(&mut todo, &other));
+        assert_eq!(todo.descriptions(), vec!["b", "c", "d"]);
+
+        let mut todo = list_of(&["a", "b"]);
+        todo.set_max_items(Some(3));
+        assert!(merge(&mut todo, &other, None));
+        assert_eq!(todo.descriptions(), vec!["b", "c", "d"]);
+
+        let mut todo = list_of(&["a", "b"]);
+        todo.set_max_items(Some(3));
+        assert!(import(&mut todo, &csv, false));
+        assert_eq!(todo.descriptions(), vec!["b", "e", "f"]);
+    }
+
+    #[test]
+    fn add_at_the_top_skipping_a_duplicate() {
+        let mut todo = list_of(&["x", "b"]);
+        let options = AddOptions {
+            at_top: true,
+            ..AddOptions::default()
+        };
+        add(&mut todo, &strings(&["a", "b", "c"]), options);
+        let order: Vec<&str> = todo.items().iter().map(|item| item.description()).collect();
+        assert_eq!(order, vec!["a", "c", "x", "b"]);
     }
 }
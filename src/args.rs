@@ -0,0 +1,132 @@
+//! Module responsible for parsing the arguments given by the user
+
+/// Arguments given by the user, without the program name
+pub struct Args {
+    values: Vec<String>,
+}
+
+impl Args {
+    /// Build the arguments from a list of values
+    pub fn build(values: Vec<String>) -> Args {
+        Args { values }
+    }
+
+    /// Build the arguments from the ones the program was called with
+    pub fn from_env() -> Args {
+        Args::build(std::env::args().skip(1).collect())
+    }
+
+    /// Build the arguments from a line, split like a shell would
+    /// Words can be quoted with double or single quotes to keep their spaces
+    pub fn parse_line(line: &str) -> Args {
+        let mut values = Vec::new();
+        let mut word = String::new();
+        let mut in_word = false;
+        let mut quote: Option<char> = None;
+
+        for c in line.chars() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), c) => word.push(c),
+                (None, '"') | (None, '\'') => {
+                    quote = Some(c);
+                    in_word = true;
+                }
+                (None, c) if c.is_whitespace() => {
+                    if in_word {
+                        values.push(std::mem::take(&mut word));
+                        in_word = false;
+                    }
+                }
+                (None, c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+            }
+        }
+        if in_word {
+            values.push(word);
+        }
+        Args::build(values)
+    }
+
+    /// Remove a flag from the arguments
+    /// Return if the flag was given
+    pub fn flag(&mut self, name: &str) -> bool {
+        let before = self.values.len();
+        self.values.retain(|value| value != name);
+        before != self.values.len()
+    }
+
+    /// Remove an option from the arguments and return its value
+    /// Both `--name value` and `--name=value` are accepted
+    pub fn option(&mut self, name: &str) -> Option<String> {
+        let prefix = format!("{}=", name);
+        let index = self
+            .values
+            .iter()
+            .position(|value| value == name || value.starts_with(&prefix))?;
+
+        let value = self.values.remove(index);
+        match value.strip_prefix(&prefix) {
+            Some(inline) => Some(String::from(inline)),
+            None if index < self.values.len() => Some(self.values.remove(index)),
+            None => None,
+        }
+    }
+
+    /// All the arguments that are not flags or options
+    pub fn positional(&self) -> &[String] {
+        &self.values
+    }
+
+    /// Get the positional argument in the given index
+    pub fn nth(&self, index: usize) -> Option<&str> {
+        self.values.get(index).map(|value| value.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Args {
+        Args::build(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn options_in_both_forms() {
+        let mut given = args(&["list", "--tag", "work", "--format=json", "--owner"]);
+        assert_eq!(given.option("--tag").as_deref(), Some("work"));
+        assert_eq!(given.option("--format").as_deref(), Some("json"));
+        assert_eq!(given.option("--owner"), None);
+        assert_eq!(given.option("--tag"), None);
+        assert_eq!(given.positional(), ["list"]);
+    }
+
+    #[test]
+    fn flags_are_removed() {
+        let mut given = args(&["list", "--pending", "--pending"]);
+        assert!(given.flag("--pending"));
+        assert!(!given.flag("--pending"));
+        assert_eq!(given.len(), 1);
+    }
+
+    #[test]
+    fn line_with_quoted_words() {
+        let args = Args::parse_line("  add \"buy milk\" 'and bread'  --at-top");
+        assert_eq!(
+            args.positional(),
+            ["add", "buy milk", "and bread", "--at-top"]
+        );
+        assert!(Args::parse_line("   ").is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Module responsible for the configuration given by the user
+use serde::Deserialize;
+use std::io::IsTerminal;
+use unicode_width::UnicodeWidthStr;
+
+/// Configuration read from the config file, every field is optional
+#[derive(Deserialize, Debug, Default)]
+pub struct Config {
+    /// Markers of the done and pending items, as "done/pending"
+    pub markers: Option<String>,
+    /// How many events are kept in the history of each item
+    pub max_history: Option<usize>,
+    /// Csv file, without extension, where the completed items are appended
+    pub archive_on_complete: Option<String>,
+    /// Completed items older than these days are archived on every run, off if not given
+    pub auto_archive_days: Option<i64>,
+    /// If the items with an empty description are dropped when saving, on by default
+    pub prune_empty_on_save: Option<bool>,
+    /// When the output is colored, as "auto", "always" or "never"
+    pub color: Option<String>,
+}
+
+impl Config {
+    /// Read the config file
+    /// If the file doesn't exist, or is invalid, the default config is used
+    pub fn read(filename: &str) -> Config {
+        let path = format!("{}.toml", filename);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|why| {
+                eprintln!(
+                    "Invalid config file {}, using the default one: {}",
+                    path, why
+                );
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        }
+    }
+}
+
+/// Markers shown inside the brackets of done and pending items
+pub struct Markers {
+    pub done: String,
+    pub pending: String,
+}
+
+impl Default for Markers {
+    fn default() -> Markers {
+        Markers {
+            done: String::from("x"),
+            pending: String::from(" "),
+        }
+    }
+}
+
+impl Markers {
+    /// Parse markers given as "done/pending", like "✔/✗" or "x/ "
+    /// Each marker must take a single cell of the terminal
+    pub fn parse(markers: &str) -> Result<Markers, String> {
+        match markers.split_once('/') {
+            Some((done, pending)) if done.width() == 1 && pending.width() == 1 => Ok(Markers {
+                done: String::from(done),
+                pending: String::from(pending),
+            }),
+            Some(_) => Err(format!(
+                "The markers \"{}\" must take a single cell each",
+                markers
+            )),
+            None => Err(format!(
+                "The markers \"{}\" must be given as \"done/pending\"",
+                markers
+            )),
+        }
+    }
+
+    /// The marker of an item, inside brackets
+    pub fn of(&self, done: bool) -> String {
+        format!("[{}]", if done { &self.done } else { &self.pending })
+    }
+}
+
+/// When the output is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Only when printing to a terminal, and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Parse the mode given as "auto", "always" or "never"
+    pub fn parse(mode: &str) -> Result<ColorMode, String> {
+        match mode.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!(
+                "The color mode \"{}\" must be auto, always or never",
+                mode
+            )),
+        }
+    }
+
+    /// Check if the output should be colored in this mode
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_of_a_single_cell() {
+        let markers = Markers::parse("✔/✗").unwrap();
+        assert_eq!(markers.of(true), "[✔]");
+        assert_eq!(markers.of(false), "[✗]");
+        assert!(Markers::parse("done/todo").is_err());
+        assert!(Markers::parse("x").is_err());
+    }
+
+    #[test]
+    fn config_from_toml() {
+        let config: Config = toml::from_str("markers = \"✔/✗\"").unwrap();
+        assert_eq!(config.markers.as_deref(), Some("✔/✗"));
+        assert!(toml::from_str::<Config>("").unwrap().markers.is_none());
+        let config: Config = toml::from_str("color = \"never\"\nauto_archive_days = 30").unwrap();
+        assert_eq!(config.color.as_deref(), Some("never"));
+        assert_eq!(config.auto_archive_days, Some(30));
+    }
+
+    #[test]
+    fn color_modes() {
+        assert_eq!(ColorMode::parse(" Always "), Ok(ColorMode::Always));
+        assert_eq!(ColorMode::parse("never"), Ok(ColorMode::Never));
+        assert!(ColorMode::parse("sometimes").is_err());
+        assert!(ColorMode::Always.enabled());
+        assert!(!ColorMode::Never.enabled());
+    }
+}
@@ -0,0 +1,53 @@
+//! Module responsible for the errors returned to the user
+use std::fmt;
+
+/// Errors that stop an action from running
+#[derive(Debug, PartialEq, Eq)]
+pub enum TodoError {
+    /// No action was given
+    MissingAction,
+    /// The action needs an item that was not given
+    MissingItem,
+    /// The given action doesn't exist, maybe the user meant a close one
+    InvalidAction(String, Option<String>),
+    /// The list file exists but couldn't be parsed
+    Parse(String),
+    /// The list file doesn't exist, and it shouldn't be created
+    NotFound(String),
+    /// The current user is needed but couldn't be found
+    UnknownUser,
+    /// The value given to an option is not valid
+    InvalidValue(String, String),
+    /// The list has the given number of problems
+    Invalid(usize),
+}
+
+impl fmt::Display for TodoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TodoError::MissingAction => write!(f, "Please specify an action"),
+            TodoError::MissingItem => write!(f, "Please specify an item"),
+            TodoError::InvalidAction(action, Some(suggestion)) => {
+                write!(
+                    f,
+                    "Unknown command '{}'. Did you mean '{}'?",
+                    action, suggestion
+                )
+            }
+            TodoError::InvalidAction(action, None) => {
+                write!(f, "The given command: {} is invalid!", action)
+            }
+            TodoError::Parse(why) => write!(f, "Aborting, the list could not be parsed: {}", why),
+            TodoError::NotFound(filename) => write!(f, "The list {}.json was not found!", filename),
+            TodoError::InvalidValue(option, value) => {
+                write!(f, "The given value: {} is invalid for {}!", value, option)
+            }
+            TodoError::Invalid(count) => write!(f, "The list has {} problem(s)!", count),
+            TodoError::UnknownUser => {
+                write!(f, "Could not find the current user, please set TODO_USER")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TodoError {}
@@ -1,7 +1,16 @@
 pub mod actions;
+pub mod args;
+pub mod config;
+pub mod error;
+pub mod render;
 
-static FILENAME: &'static str = "todo_list";
+static FILENAME: &str = "todo_list";
+static CONFIG_FILENAME: &str = "todo_config";
 
 fn main() {
-    actions::render_cli(FILENAME);
+    let config = config::Config::read(CONFIG_FILENAME);
+    if let Err(why) = actions::render_cli(args::Args::from_env(), FILENAME, &config) {
+        eprintln!("{}", why);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,208 @@
+//! Module responsible for rendering the items to the user
+use crate::config::Markers;
+use chrono::{DateTime, NaiveDate, Utc};
+use todo_list::TodoItem;
+
+/// How the items are shown to the user
+pub struct Render {
+    pub markers: Markers,
+    /// Show dates relative to today, like "in 2 days", instead of ISO dates
+    pub relative_dates: bool,
+    /// Show the header of the tables and the summary lines
+    pub header: bool,
+    /// Use colors and other terminal styles
+    pub color: bool,
+    /// Show the overdue pending items in bold red, if colors are used
+    pub highlight_overdue: bool,
+    pub today: NaiveDate,
+}
+
+/// Terminal style of the overdue items, and the one that resets it
+const OVERDUE_STYLE: &str = "\x1b[1;31m";
+const RESET_STYLE: &str = "\x1b[0m";
+
+impl Render {
+    /// Print the given items as a table
+    pub fn table(&self, items: &[&TodoItem]) {
+        for line in self.table_lines(items) {
+            println!("{}", line);
+        }
+    }
+
+    /// Render the given items as the lines of a table, with the header if `header`
+    pub fn table_lines(&self, items: &[&TodoItem]) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.header {
+            lines.push(format!(
+                "{:>4}  {:<4}  {:<12}  Description",
+                "Id", "Done", "Due"
+            ));
+        }
+        for item in items {
+            let done = self.markers.of(item.is_done());
+            let due = item.due().map(|due| self.date(due)).unwrap_or_default();
+            let description = match item.url() {
+                Some(url) => self.link(item.description(), url),
+                None => String::from(item.description()),
+            };
+            let row = format!(
+                "{:>4}  {:<4}  {:<12}  {}",
+                item.id(),
+                done,
+                due,
+                description
+            );
+            if self.color && self.highlight_overdue && item.is_overdue(self.today) {
+                lines.push(format!("{}{}{}", OVERDUE_STYLE, row, RESET_STYLE));
+            } else {
+                lines.push(row);
+            }
+        }
+        lines
+    }
+
+    /// Render a text linked to the url, clickable on the terminals that support it
+    /// Without colors the terminal may not understand the escapes, so only the text is given
+    pub fn link(&self, text: &str, url: &str) -> String {
+        if self.color {
+            format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+        } else {
+            String::from(text)
+        }
+    }
+
+    /// Render how many of the items are done, as a bar of the given width and a percentage
+    pub fn progress_bar(&self, done: usize, total: usize, width: usize) -> String {
+        let filled = (done * width).checked_div(total).unwrap_or(0);
+        let percent = (done * 100).checked_div(total).unwrap_or(0);
+        format!(
+            "[{}{}] {}%",
+            "#".repeat(filled),
+            "-".repeat(width - filled),
+            percent
+        )
+    }
+
+    /// Render a date, as ISO or relative to today
+    pub fn date(&self, date: NaiveDate) -> String {
+        if self.relative_dates {
+            humanize_days(date, self.today)
+        } else {
+            date.format("%Y-%m-%d").to_string()
+        }
+    }
+
+    /// Render a moment, as ISO or relative to today
+    pub fn datetime(&self, datetime: DateTime<Utc>) -> String {
+        if self.relative_dates {
+            humanize_days(datetime.date_naive(), self.today)
+        } else {
+            datetime.format("%Y-%m-%d %H:%M").to_string()
+        }
+    }
+}
+
+/// Describe a date relative to today, like "tomorrow", "in 2 days" or "3 days ago"
+pub fn humanize_days(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        0 => String::from("today"),
+        1 => String::from("tomorrow"),
+        -1 => String::from("yesterday"),
+        days if days > 0 => format!("in {} days", days),
+        days => format!("{} days ago", -days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_list::TodoList;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()
+    }
+
+    fn render(color: bool) -> Render {
+        Render {
+            markers: Markers::default(),
+            relative_dates: false,
+            header: true,
+            color,
+            highlight_overdue: true,
+            today: today(),
+        }
+    }
+
+    #[test]
+    fn days_relative_to_today() {
+        let days = |offset: i64| humanize_days(today() + chrono::Duration::days(offset), today());
+        assert_eq!(days(0), "today");
+        assert_eq!(days(1), "tomorrow");
+        assert_eq!(days(-1), "yesterday");
+        assert_eq!(days(3), "in 3 days");
+        assert_eq!(days(-5), "5 days ago");
+    }
+
+    #[test]
+    fn dates_as_iso_or_relative() {
+        let relative = Render {
+            relative_dates: true,
+            ..render(false)
+        };
+        let due = NaiveDate::from_ymd_opt(2024, 1, 12).unwrap();
+        assert_eq!(render(false).date(due), "2024-01-12");
+        assert_eq!(relative.date(due), "in 2 days");
+    }
+
+    #[test]
+    fn table_without_header() {
+        let mut todo = TodoList::build();
+        todo.insert(String::from("a"));
+        let rows_only = Render {
+            header: false,
+            ..render(false)
+        };
+        assert_eq!(
+            rows_only.table_lines(&todo.items()),
+            vec!["   0  [ ]                 a"]
+        );
+        assert!(rows_only.table_lines(&[]).is_empty());
+        assert_eq!(render(false).table_lines(&[]).len(), 1);
+    }
+
+    #[test]
+    fn overdue_rows_are_highlighted_with_colors() {
+        let mut todo = TodoList::build();
+        todo.insert(String::from("late"));
+        todo.insert(String::from("fine"));
+        todo.set_due(0, today().pred_opt());
+        todo.set_due(1, Some(today()));
+
+        let lines = render(true).table_lines(&todo.items());
+        assert!(lines[1].starts_with(OVERDUE_STYLE) && lines[1].ends_with(RESET_STYLE));
+        assert!(!lines[2].contains(OVERDUE_STYLE));
+
+        let plain = render(false).table_lines(&todo.items());
+        assert!(!plain[1].contains(OVERDUE_STYLE));
+        let off = Render {
+            highlight_overdue: false,
+            ..render(true)
+        };
+        assert!(!off.table_lines(&todo.items())[1].contains(OVERDUE_STYLE));
+    }
+
+    #[test]
+    fn progress_bar_of_an_empty_list() {
+        assert_eq!(render(false).progress_bar(1, 4, 8), "[##------] 25%");
+        assert_eq!(render(false).progress_bar(0, 0, 4), "[----] 0%");
+    }
+
+    #[test]
+    fn links_only_with_colors() {
+        assert_eq!(
+            render(true).link("pr", "https://example.com"),
+            "\x1b]8;;https://example.com\x1b\\pr\x1b]8;;\x1b\\"
+        );
+        assert_eq!(render(false).link("pr", "https://example.com"), "pr");
+    }
+}
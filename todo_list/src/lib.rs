@@ -1,16 +1,125 @@
+use chrono::{DateTime, Datelike, Duration, IsoWeek, NaiveDate, Utc};
+use indexmap::{map::Entry, IndexMap};
+use rand::{seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use std::{
-    collections::{hash_map::Entry, HashMap},
-    fs::write,
-    io::{BufReader, ErrorKind, Read},
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    fs::{copy, create_dir_all, read_dir, remove_file, write},
+    io::{BufReader, ErrorKind, Read, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TodoItem {
     id: u32,
     description: String,
     done: bool,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    history: VecDeque<HistoryEvent>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Priority::is_default")]
+    priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completion_note: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    progress: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    actual_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+/// Progress of an item that is complete
+pub const FULL_PROGRESS: u8 = 100;
+
+/// How urgent a TodoItem is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Priority {
+    const LEVELS: [Priority; 3] = [Priority::Low, Priority::Medium, Priority::High];
+
+    pub fn is_default(&self) -> bool {
+        *self == Priority::default()
+    }
+
+    /// The priority the given number of levels up, or down if negative
+    /// It stops at the lowest and the highest priority
+    pub fn step(self, delta: i32) -> Priority {
+        let index = Priority::LEVELS
+            .iter()
+            .position(|level| *level == self)
+            .unwrap_or(1) as i32;
+        let index = (index + delta).clamp(0, Priority::LEVELS.len() as i32 - 1);
+        Priority::LEVELS[index as usize]
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "low"),
+            Priority::Medium => write!(f, "medium"),
+            Priority::High => write!(f, "high"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(priority: &str) -> Result<Priority, String> {
+        match priority.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            _ => Err(format!(
+                "The given priority: {} is not low, medium or high!",
+                priority
+            )),
+        }
+    }
+}
+
+/// Something that happened to a TodoItem
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEvent {
+    at: DateTime<Utc>,
+    event: String,
+}
+
+impl HistoryEvent {
+    pub fn at(&self) -> DateTime<Utc> {
+        self.at
+    }
+
+    pub fn event(&self) -> &str {
+        &self.event
+    }
 }
 
 impl TodoItem {
@@ -20,6 +129,17 @@ impl TodoItem {
             id: next_id,
             description,
             done: false,
+            created_at: Some(Utc::now()),
+            completed_at: None,
+            owner: None,
+            history: VecDeque::new(),
+            tags: BTreeSet::new(),
+            due: None,
+            priority: Priority::default(),
+            completion_note: None,
+            progress: 0,
+            actual_minutes: None,
+            url: None,
         }
     }
 
@@ -35,9 +155,151 @@ impl TodoItem {
         self.done
     }
 
+    /// When the item was created, unknown for items older than this field
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+
+    /// When the item was completed, if it is done
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+    }
+
+    /// Note about the outcome, given when the item was completed
+    pub fn completion_note(&self) -> Option<&str> {
+        self.completion_note.as_deref()
+    }
+
+    /// Who is responsible for the item, if anyone
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// When the item should be done, if it has a due date
+    pub fn due(&self) -> Option<NaiveDate> {
+        self.due
+    }
+
+    /// Check if the item is still pending after its due date
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.done && self.due.is_some_and(|due| due < today)
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// How much of the item is done, as a percentage
+    pub fn progress(&self) -> u8 {
+        self.progress
+    }
+
+    /// How many minutes were actually spent on the item, if recorded
+    pub fn actual_minutes(&self) -> Option<u32> {
+        self.actual_minutes
+    }
+
+    /// Link the item refers to, like a pull request or a ticket
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn tags(&self) -> &BTreeSet<String> {
+        &self.tags
+    }
+
+    /// Everything that happened to the item, from the oldest to the most recent
+    pub fn history(&self) -> &VecDeque<HistoryEvent> {
+        &self.history
+    }
+
+    /// Record something that happened to the item
+    /// Only the `max` most recent events are kept, the oldest are evicted
+    pub fn push_event(&mut self, event: &str, max: usize) {
+        self.history.push_back(HistoryEvent {
+            at: Utc::now(),
+            event: String::from(event),
+        });
+        self.truncate_history(max);
+    }
+
+    /// Evict the oldest events, keeping only the `max` most recent ones
+    fn truncate_history(&mut self, max: usize) {
+        while self.history.len() > max {
+            self.history.pop_front();
+        }
+    }
+
+    /// Return the item alone in json, as it is kept in the list
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self)
+    }
+
+    /// Return the item alone in json pretty
+    pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self)
+    }
+
+    /// How long the item took from creation to completion
+    pub fn completion_time(&self) -> Option<Duration> {
+        Some(self.completed_at? - self.created_at?)
+    }
+
     /// Update a TodoItem
+    /// Completing it records the moment, reopening it forgets it
     pub fn update(&mut self) {
-        self.done = !self.done;
+        if self.done {
+            self.reopen()
+        } else {
+            self.complete(None)
+        }
+    }
+
+    /// Complete a TodoItem, with an optional note about the outcome
+    pub fn complete(&mut self, note: Option<String>) {
+        self.done = true;
+        self.completed_at = Some(Utc::now());
+        self.completion_note = note;
+    }
+
+    /// Reopen a TodoItem, forgetting when it was completed and its note
+    pub fn reopen(&mut self) {
+        self.done = false;
+        self.completed_at = None;
+        self.completion_note = None;
+    }
+
+    /// Update a TodoItem, recording it in the history
+    fn update_with_history(&mut self, max_history: usize) -> bool {
+        self.update();
+        let event = if self.done { "completed" } else { "reopened" };
+        self.push_event(event, max_history);
+        self.done
+    }
+
+    /// Order in which the items should be worked on, the first is the next one:
+    /// 1. higher priority first
+    /// 2. earlier due date first, items without a due date last
+    /// 3. earlier creation first, items without a creation date last
+    /// 4. lower id first
+    ///
+    /// Ids are unique, so two different items are never equal
+    pub fn next_task_order(&self, other: &TodoItem) -> Ordering {
+        fn none_last<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| none_last(self.due, other.due))
+            .then_with(|| none_last(self.created_at, other.created_at))
+            .then_with(|| self.id.cmp(&other.id))
     }
 
     /// Header off a TodoItem to a line of a csv
@@ -46,210 +308,2438 @@ impl TodoItem {
     }
 
     /// Convert a TodoItem to a line of a csv
+    /// The description is quoted if it has commas, quotes or line breaks
     pub fn elem_in_csv(&self) -> String {
-        format!("{},{},{}", self.id, self.description, self.done)
+        format!("{},{},{}", self.id, quote_csv(&self.description), self.done)
     }
 
-    /// Clone
-    pub fn clone(&self) -> TodoItem {
-        TodoItem {
-            id: self.id,
-            description: String::from(&self.description),
-            done: self.done,
+    /// Append the TodoItem to a csv file
+    /// If the file doesn't exist it is created, with the header
+    pub fn append_csv(&self, filename: &str) -> Result<(), std::io::Error> {
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(format!("{}.csv", filename))?;
+
+        if f.metadata()?.len() == 0 {
+            writeln!(f, "{}", TodoItem::header_of_csv())?;
         }
+        writeln!(f, "{}", self.elem_in_csv())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct TodoList {
-    list: HashMap<String, TodoItem>,
-    next_id: u32,
+/// Minimum number of completed items to trust the average completion time
+pub const MIN_COMPLETED_FOR_AVERAGE: usize = 3;
+
+/// Ranges of completion times used by `completion_time_buckets`
+/// Each one holds the items that took less than its number of days, the last one has no limit
+pub const COMPLETION_TIME_BUCKETS: [(&str, Option<i64>); 4] = [
+    ("<1d", Some(1)),
+    ("1-3d", Some(3)),
+    ("3-7d", Some(7)),
+    (">7d", None),
+];
+
+/// Number of events kept in the history of each item, if not configured
+pub const DEFAULT_MAX_HISTORY: usize = 50;
+
+/// Filters to select some of the items, an item must match all the given ones
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    /// Only the completed items, or only the pending ones
+    pub done: Option<bool>,
+    /// Only the items of this owner
+    pub owner: Option<String>,
+    /// Only the items with this tag
+    pub tag: Option<String>,
+    /// Only the items with a greater id, the ones added after it
+    pub since_id: Option<u32>,
 }
 
-impl TodoList {
-    /// Build a empty TodoList
-    /// The id is always start with 0
-    pub fn build() -> TodoList {
-        TodoList {
-            list: HashMap::new(),
-            next_id: 0,
-        }
+impl Query {
+    /// Check if the query has no filters, so it matches every item
+    pub fn is_empty(&self) -> bool {
+        self.done.is_none() && self.owner.is_none() && self.tag.is_none() && self.since_id.is_none()
     }
 
-    /// Get todo item by description
-    pub fn get_item_by_description(&self, todo_description: String) -> Option<&TodoItem> {
-        match self.list.get(&todo_description) {
-            Some(value) => Some(&value),
-            None => None,
-        }
+    /// Check if the item matches all the filters of the query
+    pub fn matches(&self, item: &TodoItem) -> bool {
+        self.done.is_none_or(|done| item.done == done)
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| item.owner.as_ref() == Some(owner))
+            && self.tag.as_ref().is_none_or(|tag| item.tags.contains(tag))
+            && self.since_id.is_none_or(|id| item.id > id)
     }
+}
 
-    /// Get todo item by id
-    pub fn get_item_by_id(&self, todo_id: u32) -> Option<&TodoItem> {
-        for elem in self.list.values() {
-            if elem.id == todo_id {
-                return Some(&elem);
+/// A problem found by `TodoList::validate`, usually from editing the file by hand
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationError {
+    /// The item has no description
+    EmptyDescription { id: u32 },
+    /// The items have the same description, ignoring the case and whitespace
+    DuplicateDescription { description: String, ids: Vec<u32> },
+    /// More than one item has the same id
+    DuplicateId { id: u32 },
+    /// The id is not below the next id, so a new item could take it
+    IdNotBelowNext { id: u32, next_id: u32 },
+    /// The item is kept under a key that is not its description
+    KeyMismatch { id: u32, key: String },
+    /// The item is pending but has a completion date
+    PendingWithCompletion { id: u32 },
+    /// The progress of the item is over `FULL_PROGRESS`
+    ProgressOverFull { id: u32, progress: u8 },
+    /// The items don't match the checksum saved with them
+    ChecksumMismatch,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::EmptyDescription { id } => {
+                write!(f, "Todo item {} has an empty description", id)
+            }
+            ValidationError::DuplicateDescription { description, ids } => {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                write!(
+                    f,
+                    "Todo items {} have the same description: {}",
+                    ids.join(", "),
+                    description
+                )
+            }
+            ValidationError::DuplicateId { id } => {
+                write!(f, "More than one todo item has the id {}", id)
+            }
+            ValidationError::IdNotBelowNext { id, next_id } => {
+                write!(f, "Todo item {} is not below the next id {}", id, next_id)
+            }
+            ValidationError::KeyMismatch { id, key } => {
+                write!(f, "Todo item {} is kept under the wrong key: {}", id, key)
+            }
+            ValidationError::PendingWithCompletion { id } => {
+                write!(f, "Todo item {} is pending but has a completion date", id)
+            }
+            ValidationError::ProgressOverFull { id, progress } => {
+                write!(f, "Todo item {} has a progress of {}%", id, progress)
+            }
+            ValidationError::ChecksumMismatch => {
+                write!(f, "The items don't match the checksum saved with them")
             }
         }
-        None
     }
+}
 
-    /// Update one todo item according the given description
-    pub fn update_todo_item_description(&mut self, todo_description: String) -> Option<bool> {
-        match self.list.get_mut(&todo_description.to_ascii_lowercase()) {
-            Some(v) => {
-                v.update();
-                Some(v.is_done())
-            }
-            None => None,
+/// Differences between two lists, matching the items by id
+#[derive(Serialize, Debug, Default)]
+pub struct Diff {
+    /// Items only in the new list
+    pub added: Vec<TodoItem>,
+    /// Items only in the old list
+    pub removed: Vec<TodoItem>,
+    /// Items in both lists, but different
+    pub changed: Vec<Change>,
+}
+
+/// An item as it was in the old list and as it is in the new one
+#[derive(Serialize, Debug)]
+pub struct Change {
+    pub before: TodoItem,
+    pub after: TodoItem,
+}
+
+impl Diff {
+    /// Check if the lists have the same items
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// What `TodoList::compact` changed
+#[derive(Serialize, Debug, Default)]
+pub struct CompactReport {
+    /// Number of descriptions that had whitespace trimmed
+    pub trimmed: usize,
+    /// The duplicated items that were removed
+    pub removed: Vec<TodoItem>,
+    /// Number of items that got a new id
+    pub renumbered: usize,
+}
+
+impl CompactReport {
+    /// Check if compacting changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.trimmed == 0 && self.removed.is_empty() && self.renumbered == 0
+    }
+}
+
+/// Format of a file where a TodoList is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// What to do when merging an item with the same description as an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep the existing item
+    #[default]
+    Skip,
+    /// Keep the item created most recently
+    KeepNewer,
+    /// Keep the completed item, if only one of them is
+    KeepDone,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    fn from_str(strategy: &str) -> Result<MergeStrategy, String> {
+        match strategy.trim().to_ascii_lowercase().as_str() {
+            "skip" => Ok(MergeStrategy::Skip),
+            "keep-newer" | "newer" => Ok(MergeStrategy::KeepNewer),
+            "keep-done" | "done" => Ok(MergeStrategy::KeepDone),
+            _ => Err(format!(
+                "The given strategy: {} is not skip, keep-newer or keep-done!",
+                strategy
+            )),
         }
     }
+}
 
-    /// Update one todo item according the given id
-    pub fn update_todo_item_id(&mut self, id: u32) -> Option<bool> {
-        for elem in self.list.values_mut() {
-            if elem.id == id {
-                elem.update();
-                return Some(elem.is_done());
-            }
+/// What `TodoList::merge` changed
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Number of items that were new, added with a new id
+    pub added: usize,
+    /// Number of existing items replaced by the merged ones
+    pub replaced: usize,
+    /// Number of merged items left out, because an item with the same description was kept
+    pub skipped: usize,
+}
+
+/// Information about the list itself, kept in the file
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Metadata {
+    /// Checksum of the items when the list was saved
+    checksum: Option<u64>,
+    /// Maximum number of items the list keeps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_items: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(from = "StoredTodoList")]
+pub struct TodoList {
+    list: IndexMap<String, TodoItem>,
+    next_id: u32,
+    metadata: Metadata,
+    max_history: usize,
+    /// If the items with an empty description are dropped when saving
+    prune_empty_on_save: bool,
+    /// Key in `list` of each id, so the lookups by id don't scan the list
+    ids: HashMap<u32, String>,
+}
+
+/// The fields of a TodoList that are kept in the file
+/// The items are an array sorted by id, so two saves of the same list are identical
+/// If the list is in other order, like after `--at-top`, the ids in that order are kept too
+#[derive(Serialize)]
+struct SavedTodoList<'a> {
+    items: Vec<&'a TodoItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    order: Vec<u32>,
+    next_id: u32,
+    metadata: &'a Metadata,
+}
+
+impl Serialize for TodoList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let order: Vec<u32> = self.list.values().map(|item| item.id).collect();
+        let mut items = self.items();
+        items.sort_by_key(|item| item.id);
+        let sorted = order.windows(2).all(|ids| ids[0] < ids[1]);
+        SavedTodoList {
+            items,
+            order: if sorted { Vec::new() } else { order },
+            next_id: self.next_id,
+            metadata: &self.metadata,
         }
-        None
+        .serialize(serializer)
     }
+}
 
-    /// Insert a new item into our Todo_list.
-    /// We will consider we pass false as value
-    pub fn insert(&mut self, todo_description: String) -> bool {
-        match self.list.entry(todo_description.to_ascii_lowercase()) {
-            Entry::Vacant(elem) => {
-                let todo_item =
-                    TodoItem::build(self.next_id, todo_description.to_ascii_lowercase());
-                elem.insert(todo_item);
-                self.next_id += 1;
-                true
+/// The fields of a TodoList read from the file
+/// The lists saved before the items were an array keep them in the `list` map
+#[derive(Deserialize)]
+struct StoredTodoList {
+    #[serde(default)]
+    list: Option<IndexMap<String, TodoItem>>,
+    #[serde(default)]
+    items: Vec<TodoItem>,
+    #[serde(default)]
+    order: Vec<u32>,
+    next_id: u32,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+impl From<StoredTodoList> for TodoList {
+    fn from(stored: StoredTodoList) -> TodoList {
+        let list = match stored.list {
+            Some(list) => list,
+            None => {
+                let position: HashMap<u32, usize> = stored
+                    .order
+                    .iter()
+                    .enumerate()
+                    .map(|(index, id)| (*id, index))
+                    .collect();
+                let mut items = stored.items;
+                items.sort_by_key(|item| {
+                    (
+                        position.get(&item.id).copied().unwrap_or(usize::MAX),
+                        item.id,
+                    )
+                });
+                // unlike the old map, the array may have descriptions that differ only by
+                // case, so those items keep a key with their id to be found by `validate`
+                let mut list = IndexMap::new();
+                for item in items {
+                    let description = item.description.to_ascii_lowercase();
+                    let key = if list.contains_key(&description) {
+                        format!("{}#{}", description, item.id)
+                    } else {
+                        description
+                    };
+                    list.insert(key, item);
+                }
+                list
             }
-            Entry::Occupied(_) => false,
-        }
+        };
+        TodoList::from_parts(list, stored.next_id, stored.metadata)
     }
+}
 
-    /// Remove a item from our Todo_list by description
-    pub fn remove_by_description(&mut self, todo_description: String) -> Option<TodoItem> {
-        self.list.remove(&todo_description.to_ascii_lowercase())
+impl TodoList {
+    /// Build a empty TodoList
+    /// The id is always start with 0
+    pub fn build() -> TodoList {
+        TodoList::from_parts(IndexMap::new(), 0, Metadata::default())
     }
 
-    /// Remove a item from our Todo_list by id
-    pub fn remove_by_id(&mut self, id: u32) -> Option<TodoItem> {
-        let mut index: Option<String> = None;
-        for (description, elem) in self.list.iter_mut() {
-            if elem.id == id {
-                index = Some(String::from(description));
-                break;
-            }
-        }
+    /// Build a TodoList from its items, indexing them by id
+    fn from_parts(list: IndexMap<String, TodoItem>, next_id: u32, metadata: Metadata) -> TodoList {
+        let mut todo = TodoList {
+            list,
+            next_id,
+            metadata,
+            max_history: DEFAULT_MAX_HISTORY,
+            prune_empty_on_save: true,
+            ids: HashMap::new(),
+        };
+        todo.reindex();
+        todo
+    }
 
-        if index.is_some() {
-            return self.list.remove(&index.unwrap());
-        }
-        None
+    /// Index the items by id again, after their keys or ids changed
+    fn reindex(&mut self) {
+        self.ids = self
+            .list
+            .iter()
+            .map(|(key, item)| (item.id, String::from(key)))
+            .collect();
     }
 
-    /// Return all the struct in json  pretty
-    pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(&self)
+    /// Get the todo item with the given id, to change it
+    fn get_mut_by_id(&mut self, id: u32) -> Option<&mut TodoItem> {
+        let key = self.ids.get(&id)?;
+        self.list.get_mut(key)
     }
 
-    /// Return all the struct in json
-    pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string(&self)
+    /// Checksum of the items, independent of the order they are stored
+    /// It uses FNV-1a over the json of the items sorted by id,
+    /// so it is stable between runs and versions of the program
+    pub fn checksum(&self) -> u64 {
+        let mut items = self.items();
+        items.sort_by_key(|item| item.id);
+        let canonical = serde_json::to_string(&items).unwrap_or_default();
+
+        canonical.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
     }
 
-    /// Read the default file, and return the all struct
-    /// If the file don't exist we will create one
-    /// In this case the file is JSON
-    pub fn read_json(filename: &str) -> Result<TodoList, std::io::Error> {
-        let f = std::fs::OpenOptions::new()
-            .write(true)
-            .read(true)
-            .open(format!("{}.json", &filename));
+    /// Check if the items still match the checksum stored when the list was saved
+    /// Lists saved without a checksum are always valid
+    pub fn verify_checksum(&self) -> bool {
+        match self.metadata.checksum {
+            Some(checksum) => checksum == self.checksum(),
+            None => true,
+        }
+    }
 
-        if f.is_err() {
-            return Err(f.err().unwrap());
+    /// Set how many events are kept in the history of each item
+    /// The items that already have more events lose the oldest ones
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history;
+        for item in self.list.values_mut() {
+            item.truncate_history(max_history);
         }
+    }
+
+    /// Set if the items with an empty description are dropped when saving, on by default
+    pub fn set_prune_empty_on_save(&mut self, prune: bool) {
+        self.prune_empty_on_save = prune;
+    }
+
+    /// Remove all the items with an empty description
+    /// Return how many were removed
+    pub fn prune_empty(&mut self) -> usize {
+        let before = self.list.len();
+        self.list
+            .retain(|_, item| !item.description.trim().is_empty());
+        let list = &self.list;
+        self.ids.retain(|_, key| list.contains_key(key));
+        before - self.list.len()
+    }
+
+    /// Number of items in the list
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
 
-        let f = f.unwrap();
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
 
-        let buf_reader = BufReader::new(f);
+    /// Get all the todo items, in the order of the list
+    pub fn items(&self) -> Vec<&TodoItem> {
+        self.list.values().collect()
+    }
 
-        let result = serde_json::from_reader(buf_reader);
+    /// Get a page of at most `limit` todo items with an id greater than `after`, in the order of their ids
+    /// The first page is given by no `after`, as the ids start at 0
+    /// The cursor of the next page, the last id of this one, is given only if more items remain
+    /// The `limit` can't be 0, as an empty page has no cursor to give
+    pub fn split_at_id(
+        &self,
+        after: Option<u32>,
+        limit: NonZeroUsize,
+    ) -> (Vec<&TodoItem>, Option<u32>) {
+        let limit = limit.get();
+        let mut items: Vec<&TodoItem> = self
+            .list
+            .values()
+            .filter(|item| after.is_none_or(|after| item.id > after))
+            .collect();
+        items.sort_by_key(|item| item.id);
 
-        if result.is_err() {
-            let phrase = format!(
-                "Error reading / opening file ::: {}",
-                result.err().unwrap().to_string()
-            );
-            let error = std::io::Error::new(ErrorKind::Other, phrase);
-            Err(error)
+        let more = items.len() > limit;
+        items.truncate(limit);
+        let cursor = if more {
+            items.last().map(|item| item.id)
         } else {
-            Ok(result.unwrap())
-        }
+            None
+        };
+        (items, cursor)
     }
 
-    /// Save all the struct in a json file
-    pub fn save_json(&self, filename: &str) -> Result<(), std::io::Error> {
-        let path = format!("{}.json", filename);
-        let todo_list_json = serde_json::to_string_pretty(&self).unwrap();
-        write(path, &todo_list_json)
+    /// Get the descriptions of all the todo items, in the order of their ids
+    pub fn descriptions(&self) -> Vec<&str> {
+        let mut items = self.items();
+        items.sort_by_key(|item| item.id);
+        items.into_iter().map(|item| item.description()).collect()
     }
 
-    /// Save all the struct in a csv file
-    pub fn save_csv(&self, filename: &str) -> Result<(), std::io::Error> {
-        let mut content = String::new();
+    /// Average time an item takes from creation to completion
+    /// Only known if at least `MIN_COMPLETED_FOR_AVERAGE` items have both dates
+    pub fn avg_completion_time(&self) -> Option<Duration> {
+        let times: Vec<Duration> = self
+            .list
+            .values()
+            .filter_map(|item| item.completion_time())
+            .collect();
 
-        content.push_str(&format!("{}\n", TodoItem::header_of_csv()));
-        for value in self.list.values() {
-            let record = format!("{}\n", value.elem_in_csv());
-            content.push_str(&record);
+        if times.len() < MIN_COMPLETED_FOR_AVERAGE {
+            return None;
         }
-        std::fs::write(format!("{}.csv", filename), content)
+
+        let total = times.iter().fold(Duration::zero(), |acc, time| acc + *time);
+        Some(total / times.len() as i32)
     }
 
-    /// Read the default file, and return the all struct
-    /// If the file don't exist we will create one
-    /// In this case the file is CSV
-    pub fn read_csv(filename: &str) -> Result<TodoList, std::io::Error> {
-        let mut f = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(format!("{}.csv", filename))?;
+    /// Forecast when all the pending items will be done,
+    /// considering they keep being completed at the average rate
+    pub fn forecast(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let average = self.avg_completion_time()?;
+        let pending = self.list.values().filter(|item| !item.done).count();
+        Some(now + average * pending as i32)
+    }
 
-        let mut id_max = 0;
-        let mut content = String::new();
+    /// Get all the todo items whose description contains the given term
+    /// The match ignores the case unless `case_sensitive` is given
+    pub fn search(&self, term: &str, case_sensitive: bool) -> Vec<&TodoItem> {
+        let term = if case_sensitive {
+            String::from(term)
+        } else {
+            term.to_lowercase()
+        };
 
-        f.read_to_string(&mut content)?;
-        let map: HashMap<String, TodoItem> = content
-            .lines()
-            .skip(1)
-            .map(|line| line.splitn(3, ',').collect::<Vec<&str>>())
-            .map(|v| (v[0], v[1], v[2]))
-            .map(|(id, description, done)| {
-                let number_id = id.trim().parse().unwrap();
-                if id_max < number_id {
-                    id_max = number_id
+        self.items()
+            .into_iter()
+            .filter(|item| {
+                if case_sensitive {
+                    item.description.contains(&term)
+                } else {
+                    item.description.to_lowercase().contains(&term)
                 }
-
-                (
-                    String::from(description),
-                    TodoItem {
-                        id: number_id,
-                        description: String::from(description),
-                        done: String::from(done) == "true",
-                    },
-                )
             })
-            .collect();
-        Ok(TodoList {
-            list: map,
-            next_id: id_max + 1,
+            .collect()
+    }
+
+    /// Split the list into the completed and the pending items, in this order
+    /// Both lists keep the ids and the next id of the original one
+    pub fn partition(self) -> (TodoList, TodoList) {
+        let (done, pending): (IndexMap<String, TodoItem>, IndexMap<String, TodoItem>) =
+            self.list.into_iter().partition(|(_, item)| item.done);
+
+        let mut done = TodoList::from_parts(done, self.next_id, self.metadata.clone());
+        let mut pending = TodoList::from_parts(pending, self.next_id, self.metadata);
+        done.max_history = self.max_history;
+        pending.max_history = self.max_history;
+        (done, pending)
+    }
+
+    /// Get all the todo items owned by the given owner
+    pub fn filter_by_owner(&self, owner: &str) -> Vec<&TodoItem> {
+        self.query(&Query {
+            owner: Some(String::from(owner)),
+            ..Query::default()
         })
     }
+
+    /// Get all the todo items that match the query, in the order of the list
+    pub fn query(&self, query: &Query) -> Vec<&TodoItem> {
+        self.list
+            .values()
+            .filter(|item| query.matches(item))
+            .collect()
+    }
+
+    /// Give the todo item with the given id to an owner, or to no one
+    /// Return if the item exists
+    pub fn assign(&mut self, id: u32, owner: Option<String>) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                let event = match &owner {
+                    Some(owner) => format!("assigned to {}", owner),
+                    None => String::from("unassigned"),
+                };
+                elem.push_event(&event, max_history);
+                elem.owner = owner;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// One line summary of the list, like "3 items, 1 done, 2 pending"
+    pub fn summary_string(&self) -> String {
+        let total = self.list.len();
+        let done = self.list.values().filter(|item| item.done).count();
+        format!(
+            "{} {}, {} done, {} pending",
+            total,
+            if total == 1 { "item" } else { "items" },
+            done,
+            total - done
+        )
+    }
+
+    /// Get all the todo items completed before the given moment, in the order of the list
+    /// Items without a completion date are never included
+    pub fn completed_before(&self, cutoff: DateTime<Utc>) -> Vec<&TodoItem> {
+        self.list
+            .values()
+            .filter(|item| item.done && item.completed_at.is_some_and(|at| at < cutoff))
+            .collect()
+    }
+
+    /// Get all the pending todo items past their due date, in the order of the list
+    pub fn overdue(&self, today: NaiveDate) -> Vec<&TodoItem> {
+        self.list
+            .values()
+            .filter(|item| item.is_overdue(today))
+            .collect()
+    }
+
+    /// Number of items completed in each ISO week between the given dates
+    /// Every week of the range is present, even the ones without completions
+    pub fn stats_timeline(&self, from: NaiveDate, to: NaiveDate) -> Vec<(IsoWeek, usize)> {
+        let mut timeline = Vec::new();
+        let mut monday = from - Duration::days(from.weekday().num_days_from_monday() as i64);
+
+        while monday <= to {
+            let week = monday.iso_week();
+            let count = self
+                .list
+                .values()
+                .filter_map(|item| item.completed_at)
+                .filter(|date| date.date_naive().iso_week() == week)
+                .count();
+            timeline.push((week, count));
+            monday += Duration::weeks(1);
+        }
+        timeline
+    }
+
+    /// Number of completed items in each range of `COMPLETION_TIME_BUCKETS`
+    /// Items without both dates are not counted, every range is present even if empty
+    pub fn completion_time_buckets(&self) -> Vec<(&'static str, usize)> {
+        let mut buckets: Vec<(&'static str, usize)> = COMPLETION_TIME_BUCKETS
+            .iter()
+            .map(|(label, _)| (*label, 0))
+            .collect();
+
+        for time in self.list.values().filter_map(|item| item.completion_time()) {
+            let index = COMPLETION_TIME_BUCKETS
+                .iter()
+                .position(|(_, days)| days.is_none_or(|days| time < Duration::days(days)))
+                .unwrap_or(COMPLETION_TIME_BUCKETS.len() - 1);
+            buckets[index].1 += 1;
+        }
+        buckets
+    }
+
+    /// Add a tag to the todo item with the given id
+    /// Return if the item exists
+    pub fn tag(&mut self, id: u32, tag: String) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                elem.push_event(&format!("tagged {}", tag), max_history);
+                elem.tags.insert(tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the due date of the todo item with the given id, or remove it
+    /// Return if the item exists
+    pub fn set_due(&mut self, id: u32, due: Option<NaiveDate>) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                let event = match due {
+                    Some(due) => format!("due on {}", due),
+                    None => String::from("no due date"),
+                };
+                elem.push_event(&event, max_history);
+                elem.due = due;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the priority of the todo item with the given id
+    /// Return if the item exists
+    pub fn set_priority(&mut self, id: u32, priority: Priority) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                elem.push_event(&format!("priority {}", priority), max_history);
+                elem.priority = priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the link of the todo item with the given id, or remove it
+    /// Return if the item exists
+    pub fn set_url(&mut self, id: u32, url: Option<String>) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                let event = match &url {
+                    Some(url) => format!("linked to {}", url),
+                    None => String::from("unlinked"),
+                };
+                elem.push_event(&event, max_history);
+                elem.url = url;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record the minutes actually spent on the todo item with the given id
+    /// Return if the item exists
+    pub fn set_actual_minutes(&mut self, id: u32, minutes: u32) -> bool {
+        let max_history = self.max_history;
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                elem.push_event(&format!("spent {} minute(s)", minutes), max_history);
+                elem.actual_minutes = Some(minutes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the progress of the todo item with the given id, up to `FULL_PROGRESS`
+    /// Reaching it completes the item, and going below it reopens a completed one
+    /// Return if the item exists
+    pub fn set_progress(&mut self, id: u32, progress: u8) -> bool {
+        let max_history = self.max_history;
+        let progress = progress.min(FULL_PROGRESS);
+        match self.get_mut_by_id(id) {
+            Some(elem) => {
+                elem.push_event(&format!("progress {}%", progress), max_history);
+                elem.progress = progress;
+                if progress == FULL_PROGRESS && !elem.done {
+                    elem.complete(None);
+                    elem.push_event("completed", max_history);
+                } else if progress < FULL_PROGRESS && elem.done {
+                    elem.reopen();
+                    elem.push_event("reopened", max_history);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Raise the priority of the todo item with the given id some levels, or lower it if negative
+    /// Return the priority before and after, which are the same if it was at the limit
+    pub fn adjust_priority(&mut self, id: u32, delta: i32) -> Option<(Priority, Priority)> {
+        let before = self.get_item_by_id(id)?.priority;
+        let after = before.step(delta);
+        if after != before {
+            self.set_priority(id, after);
+        }
+        Some((before, after))
+    }
+
+    /// Rename a tag in every item that has it
+    /// Items that already have the new tag just lose the old one
+    /// Return how many items changed
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> usize {
+        let mut changed = 0;
+        for item in self.list.values_mut() {
+            if item.tags.remove(old) {
+                item.tags.insert(String::from(new));
+                item.push_event(&format!("tag {} renamed to {}", old, new), self.max_history);
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// All the distinct tags used by the items
+    pub fn all_tags(&self) -> BTreeSet<String> {
+        self.list
+            .values()
+            .flat_map(|item| item.tags.iter().cloned())
+            .collect()
+    }
+
+    /// How many items use each tag
+    pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for tag in self.list.values().flat_map(|item| item.tags.iter()) {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// How many items use each tag, the most used first and then by name
+    pub fn tag_cloud(&self) -> Vec<(String, usize)> {
+        let mut cloud: Vec<(String, usize)> = self.tag_counts().into_iter().collect();
+        // the counts come sorted by name, and the sort is stable
+        cloud.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        cloud
+    }
+
+    /// The pending todo item to work on next, see `TodoItem::next_task_order`
+    pub fn next_task(&self) -> Option<&TodoItem> {
+        self.list
+            .values()
+            .filter(|item| !item.done)
+            .min_by(|a, b| a.next_task_order(b))
+    }
+
+    /// Pick a random pending todo item, if there is any
+    pub fn random_pending<R: Rng>(&self, rng: &mut R) -> Option<&TodoItem> {
+        let pending = self.query(&Query {
+            done: Some(false),
+            ..Query::default()
+        });
+        pending.choose(rng).copied()
+    }
+
+    /// Get todo item by description
+    pub fn get_item_by_description(&self, todo_description: String) -> Option<&TodoItem> {
+        match self.list.get(&todo_description.to_ascii_lowercase()) {
+            Some(value) => Some(value),
+            None => None,
+        }
+    }
+
+    /// Get todo item by id
+    pub fn get_item_by_id(&self, todo_id: u32) -> Option<&TodoItem> {
+        let key = self.ids.get(&todo_id)?;
+        self.list.get(key)
+    }
+
+    /// Get the todo items with the given ids, in the same order
+    /// The ids without an item are skipped
+    pub fn get_many_by_ids(&self, ids: &[u32]) -> Vec<&TodoItem> {
+        ids.iter()
+            .filter_map(|id| self.get_item_by_id(*id))
+            .collect()
+    }
+
+    /// Update one todo item according the given description
+    pub fn update_todo_item_description(&mut self, todo_description: String) -> Option<bool> {
+        match self.list.get_mut(&todo_description.to_ascii_lowercase()) {
+            Some(v) => Some(v.update_with_history(self.max_history)),
+            None => None,
+        }
+    }
+
+    /// Update one todo item according the given id
+    pub fn update_todo_item_id(&mut self, id: u32) -> Option<bool> {
+        let max_history = self.max_history;
+        self.get_mut_by_id(id)
+            .map(|elem| elem.update_with_history(max_history))
+    }
+
+    /// Complete the todo item with the given id, with an optional note
+    /// Return if it was completed now, or if it was already done
+    pub fn complete_by_id(&mut self, id: u32, note: Option<String>) -> Option<bool> {
+        let max_history = self.max_history;
+        let elem = self.get_mut_by_id(id)?;
+        if elem.done {
+            return Some(false);
+        }
+        elem.complete(note);
+        elem.push_event("completed", max_history);
+        Some(true)
+    }
+
+    /// Reopen the todo item with the given id
+    /// Return if it was reopened now, or if it was already pending
+    pub fn reopen_by_id(&mut self, id: u32) -> Option<bool> {
+        let max_history = self.max_history;
+        let elem = self.get_mut_by_id(id)?;
+        if !elem.done {
+            return Some(false);
+        }
+        elem.reopen();
+        elem.push_event("reopened", max_history);
+        Some(true)
+    }
+
+    /// Insert a new item into our Todo_list.
+    /// We will consider we pass false as value
+    /// The description keeps its case, but two items can't differ only by case
+    pub fn insert(&mut self, todo_description: String) -> bool {
+        self.insert_at(todo_description, self.list.len())
+    }
+
+    /// Insert a new item into our Todo_list, in the given position of the list
+    /// The id is still the next one, whatever the position
+    pub fn insert_at(&mut self, todo_description: String, index: usize) -> bool {
+        self.insert_with_state(todo_description, index, false)
+    }
+
+    /// Insert a new item into our Todo_list, in the given position of the list
+    /// If `done` is given, the item is already completed when inserted
+    pub fn insert_with_state(
+        &mut self,
+        todo_description: String,
+        index: usize,
+        done: bool,
+    ) -> bool {
+        let index = index.min(self.list.len());
+        match self.list.entry(todo_description.to_ascii_lowercase()) {
+            Entry::Vacant(elem) => {
+                let mut todo_item = TodoItem::build(self.next_id, todo_description);
+                todo_item.push_event("created", self.max_history);
+                if done {
+                    todo_item.complete(None);
+                    todo_item.push_event("completed", self.max_history);
+                }
+                self.ids.insert(self.next_id, String::from(elem.key()));
+                elem.shift_insert(index, todo_item);
+                self.next_id += 1;
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Remove a item from our Todo_list by description
+    pub fn remove_by_description(&mut self, todo_description: String) -> Option<TodoItem> {
+        let item = self
+            .list
+            .shift_remove(&todo_description.to_ascii_lowercase())?;
+        self.ids.remove(&item.id);
+        Some(item)
+    }
+
+    /// Remove a item from our Todo_list by id
+    pub fn remove_by_id(&mut self, id: u32) -> Option<TodoItem> {
+        let key = self.ids.remove(&id)?;
+        self.list.shift_remove(&key)
+    }
+
+    /// Merge the items of another list into this one, matching them by description
+    /// New items get the next ids, and the conflicts are solved by the `strategy`
+    /// A replaced item keeps its id, so it can still be found by it
+    pub fn merge(&mut self, other: TodoList, strategy: MergeStrategy) -> MergeReport {
+        let mut report = MergeReport::default();
+        for (key, mut item) in other.list {
+            item.push_event("merged", self.max_history);
+            match self.list.entry(key) {
+                Entry::Vacant(elem) => {
+                    item.id = self.next_id;
+                    self.ids.insert(self.next_id, String::from(elem.key()));
+                    elem.insert(item);
+                    self.next_id += 1;
+                    report.added += 1;
+                }
+                Entry::Occupied(mut elem) => {
+                    let existing = elem.get();
+                    let replace = match strategy {
+                        MergeStrategy::Skip => false,
+                        MergeStrategy::KeepNewer => item.created_at > existing.created_at,
+                        MergeStrategy::KeepDone => item.done && !existing.done,
+                    };
+                    if replace {
+                        item.id = existing.id;
+                        elem.insert(item);
+                        report.replaced += 1;
+                    } else {
+                        report.skipped += 1;
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Move all the completed items to another list, merged into it so they get the next ids
+    /// Items with the same description as one in the other list stay in this one
+    /// Return how many were moved and how many stayed
+    pub fn move_all_completed_to(&mut self, target: &mut TodoList) -> (usize, usize) {
+        let mut completed: Vec<u32> = self
+            .list
+            .values()
+            .filter(|item| item.done)
+            .map(|item| item.id)
+            .collect();
+        completed.sort();
+
+        let mut moving = TodoList::build();
+        let mut stayed = 0;
+        for id in completed {
+            let key = match self.ids.get(&id) {
+                Some(key) => key.clone(),
+                None => continue,
+            };
+            if target.list.contains_key(&key) {
+                stayed += 1;
+                continue;
+            }
+            if let Some(item) = self.remove_by_id(id) {
+                moving.list.insert(key, item);
+            }
+        }
+        let report = target.merge(moving, MergeStrategy::Skip);
+        (report.added, stayed)
+    }
+
+    /// Differences from this list to a newer one, matching the items by id
+    pub fn diff(&self, newer: &TodoList) -> Diff {
+        let mut diff = Diff::default();
+        for item in self.list.values() {
+            match newer.get_item_by_id(item.id) {
+                None => diff.removed.push(item.clone()),
+                Some(after) if after != item => diff.changed.push(Change {
+                    before: item.clone(),
+                    after: after.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for item in newer.list.values() {
+            if self.get_item_by_id(item.id).is_none() {
+                diff.added.push(item.clone());
+            }
+        }
+        diff
+    }
+
+    /// Trim the whitespace around the descriptions
+    /// Items that become duplicates of others keep their key, for `dedup` to handle
+    /// Return how many descriptions changed
+    pub fn normalize(&mut self) -> usize {
+        let mut trimmed = 0;
+        for item in self.list.values_mut() {
+            let description = item.description.trim();
+            if description != item.description {
+                item.description = String::from(description);
+                trimmed += 1;
+            }
+        }
+        self.rekey();
+        trimmed
+    }
+
+    /// Check the list for problems, usually from editing the file by hand
+    /// Return all the problems found, none if the list is valid
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+        let mut by_id: Vec<(&String, &TodoItem)> = self.list.iter().collect();
+        by_id.sort_by_key(|(_, item)| item.id);
+
+        for (key, item) in by_id {
+            let id = item.id;
+            if item.description.trim().is_empty() {
+                errors.push(ValidationError::EmptyDescription { id });
+            }
+            if !seen.insert(id) {
+                errors.push(ValidationError::DuplicateId { id });
+            }
+            if id >= self.next_id {
+                errors.push(ValidationError::IdNotBelowNext {
+                    id,
+                    next_id: self.next_id,
+                });
+            }
+            if *key != item.description.to_ascii_lowercase() {
+                errors.push(ValidationError::KeyMismatch {
+                    id,
+                    key: key.clone(),
+                });
+            }
+            if !item.done && item.completed_at.is_some() {
+                errors.push(ValidationError::PendingWithCompletion { id });
+            }
+            if item.progress > FULL_PROGRESS {
+                errors.push(ValidationError::ProgressOverFull {
+                    id,
+                    progress: item.progress,
+                });
+            }
+        }
+        for (description, ids) in self.find_duplicates() {
+            errors.push(ValidationError::DuplicateDescription { description, ids });
+        }
+        if !self.verify_checksum() {
+            errors.push(ValidationError::ChecksumMismatch);
+        }
+        errors
+    }
+
+    /// Groups of the ids of the items with the same description, ignoring the case and
+    /// the whitespace around and between the words
+    /// Each group has the normalized description and at least two ids, sorted
+    pub fn find_duplicates(&self) -> Vec<(String, Vec<u32>)> {
+        let mut groups: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for item in self.list.values() {
+            let words: Vec<&str> = item.description.split_whitespace().collect();
+            groups
+                .entry(words.join(" ").to_lowercase())
+                .or_default()
+                .push(item.id);
+        }
+
+        groups
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(description, mut ids)| {
+                ids.sort();
+                (description, ids)
+            })
+            .collect()
+    }
+
+    /// Remove the items with the same description as an older one, ignoring the case
+    /// Return the removed items
+    pub fn dedup(&mut self) -> Vec<TodoItem> {
+        let mut by_id: Vec<&TodoItem> = self.list.values().collect();
+        by_id.sort_by_key(|item| item.id);
+
+        let mut seen = HashSet::new();
+        let duplicates: Vec<u32> = by_id
+            .into_iter()
+            .filter(|item| !seen.insert(item.description.to_ascii_lowercase()))
+            .map(|item| item.id)
+            .collect();
+
+        let removed = duplicates
+            .into_iter()
+            .filter_map(|id| self.remove_by_id(id))
+            .collect();
+        self.rekey();
+        removed
+    }
+
+    /// Give the items contiguous ids, starting at 0, keeping their relative order
+    /// Return how many items got a new id
+    pub fn compact_ids(&mut self) -> usize {
+        let mut by_id: Vec<&mut TodoItem> = self.list.values_mut().collect();
+        by_id.sort_by_key(|item| item.id);
+
+        let mut renumbered = 0;
+        for (new_id, item) in by_id.into_iter().enumerate() {
+            if item.id != new_id as u32 {
+                item.id = new_id as u32;
+                renumbered += 1;
+            }
+        }
+        self.next_id = self.list.len() as u32;
+        self.reindex();
+        renumbered
+    }
+
+    /// Trim the descriptions, remove the duplicates and renumber the ids
+    /// Compacting a compacted list changes nothing
+    pub fn compact(&mut self) -> CompactReport {
+        CompactReport {
+            trimmed: self.normalize(),
+            removed: self.dedup(),
+            renumbered: self.compact_ids(),
+        }
+    }
+
+    /// Key each item by its description again, keeping the order of the list
+    /// An item whose description is taken by another keeps its old key,
+    /// or a key with its id if that one is taken too
+    fn rekey(&mut self) {
+        let old = std::mem::take(&mut self.list);
+        for (key, item) in old {
+            let description = item.description.to_ascii_lowercase();
+            let key = if !self.list.contains_key(&description) {
+                description
+            } else if !self.list.contains_key(&key) {
+                key
+            } else {
+                format!("{}#{}", description, item.id)
+            };
+            self.list.insert(key, item);
+        }
+        self.reindex();
+    }
+
+    /// Maximum number of items the list keeps, if any
+    pub fn max_items(&self) -> Option<usize> {
+        self.metadata.max_items
+    }
+
+    /// Set the maximum number of items the list keeps, or remove it
+    pub fn set_max_items(&mut self, max_items: Option<usize>) {
+        self.metadata.max_items = max_items;
+    }
+
+    /// Remove items until the list fits its maximum number of items
+    /// The oldest completed item goes first, or the oldest item if none is completed
+    /// Return the removed items
+    pub fn evict_over_capacity(&mut self) -> Vec<TodoItem> {
+        self.evict_over_capacity_keeping(&[])
+    }
+
+    /// Remove items until the list fits its maximum number of items, like `evict_over_capacity`,
+    /// leaving the `kept` ids, like the ones just added, for when no other item is left
+    /// Return the removed items
+    pub fn evict_over_capacity_keeping(&mut self, kept: &[u32]) -> Vec<TodoItem> {
+        let mut evicted = Vec::new();
+        let max_items = match self.metadata.max_items {
+            Some(max_items) => max_items,
+            None => return evicted,
+        };
+
+        while self.list.len() > max_items {
+            let candidate = self
+                .eviction_candidate(kept)
+                .or_else(|| self.eviction_candidate(&[]));
+            match candidate {
+                Some(id) => evicted.extend(self.remove_by_id(id)),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    /// The id of the item to evict first, the oldest completed one or else the oldest one,
+    /// leaving out the `kept` ids
+    fn eviction_candidate(&self, kept: &[u32]) -> Option<u32> {
+        let candidates = || self.list.values().filter(|item| !kept.contains(&item.id));
+        let oldest_done = candidates()
+            .filter(|item| item.done)
+            .min_by_key(|item| item.id);
+        oldest_done
+            .or_else(|| candidates().min_by_key(|item| item.id))
+            .map(|item| item.id)
+    }
+
+    /// Remove all the completed items from our Todo_list
+    /// Return how many were removed
+    pub fn remove_completed(&mut self) -> usize {
+        let before = self.list.len();
+        self.list.retain(|_, item| !item.done);
+        let list = &self.list;
+        self.ids.retain(|_, key| list.contains_key(key));
+        before - self.list.len()
+    }
+
+    /// Remove all the items that match the query
+    /// Return the removed items, in the order of the list
+    pub fn remove_matching(&mut self, query: &Query) -> Vec<TodoItem> {
+        let (removed, kept) = std::mem::take(&mut self.list)
+            .into_iter()
+            .partition(|(_, item)| query.matches(item));
+        self.list = kept;
+        let list = &self.list;
+        self.ids.retain(|_, key| list.contains_key(key));
+        removed.into_values().collect()
+    }
+
+    /// A copy of the list without the text of the items, to share its shape
+    /// Each description becomes `task-{id}` and the completion notes and the urls are removed,
+    /// as urls often have the text of the item, the tags and the owners too if `scrub_tags`
+    /// and `scrub_owners` are given
+    /// The ids, states, dates and priorities are kept
+    pub fn anonymized(&self, scrub_tags: bool, scrub_owners: bool) -> TodoList {
+        let list = self
+            .list
+            .values()
+            .map(|item| {
+                let mut item = item.clone();
+                item.description = format!("task-{}", item.id);
+                item.completion_note = None;
+                item.url = None;
+                item.history
+                    .retain(|event| !event.event.starts_with("linked to "));
+                if scrub_tags {
+                    item.tags.clear();
+                    item.history.retain(|event| {
+                        !event.event.starts_with("tagged ") && !event.event.starts_with("tag ")
+                    });
+                }
+                if scrub_owners {
+                    item.owner = None;
+                    item.history
+                        .retain(|event| !event.event.starts_with("assigned to "));
+                }
+                (item.description.clone(), item)
+            })
+            .collect();
+        TodoList::from_parts(list, self.next_id, Metadata::default())
+    }
+
+    /// Return all the struct in json  pretty
+    pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self)
+    }
+
+    /// Return all the struct in json
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self)
+    }
+
+    /// Return all the items as a json array, in the order of the list
+    pub fn items_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.items())
+    }
+
+    /// Write all the items as a json array to the writer, one item at a time
+    /// The output is the same as `items_json`, without holding it all in memory
+    pub fn items_json_stream<W: Write>(&self, mut writer: W) -> Result<(), serde_json::Error> {
+        writer.write_all(b"[").map_err(serde_json::Error::io)?;
+        for (index, item) in self.list.values().enumerate() {
+            if index > 0 {
+                writer.write_all(b",").map_err(serde_json::Error::io)?;
+            }
+            serde_json::to_writer(&mut writer, item)?;
+        }
+        writer.write_all(b"]").map_err(serde_json::Error::io)?;
+        writer.flush().map_err(serde_json::Error::io)
+    }
+
+    /// Return all the items as a markdown checklist, in the order of the list
+    /// Each item is prefixed by its id if `include_ids` is given
+    pub fn to_markdown(&self, include_ids: bool) -> String {
+        let mut content = String::new();
+        for item in self.items() {
+            let done = if item.done { "x" } else { " " };
+            if include_ids {
+                content.push_str(&format!("- [{}] #{} {}\n", done, item.id, item.description));
+            } else {
+                content.push_str(&format!("- [{}] {}\n", done, item.description));
+            }
+        }
+        content
+    }
+
+    /// Read the list from the file, without extension, in the given format
+    /// A missing file gives a `NotFound` error, and nothing is created
+    pub fn load(filename: &str, format: Format) -> Result<TodoList, std::io::Error> {
+        match format {
+            Format::Json => TodoList::read_json(filename),
+            Format::Csv => TodoList::read_csv(filename),
+        }
+    }
+
+    /// Read the list from the file, without extension, in the given format
+    /// A missing file gives an empty list, and so does one that can't be read, with a warning
+    pub fn load_or_default(filename: &str, format: Format) -> TodoList {
+        match TodoList::load(filename, format) {
+            Ok(todo) => todo,
+            Err(why) if why.kind() == ErrorKind::NotFound => TodoList::build(),
+            Err(why) => {
+                eprintln!(
+                    "Warning: the list could not be parsed, starting with an empty one: {}",
+                    why
+                );
+                TodoList::build()
+            }
+        }
+    }
+
+    /// Read the default file, and return the all struct
+    /// If the file don't exist we will create one
+    /// In this case the file is JSON
+    /// A file that is not a valid list gives an `InvalidData` error
+    pub fn read_json(filename: &str) -> Result<TodoList, std::io::Error> {
+        let f = std::fs::OpenOptions::new()
+            .write(true)
+            .read(true)
+            .open(format!("{}.json", &filename));
+
+        if f.is_err() {
+            return Err(f.err().unwrap());
+        }
+
+        let mut content = String::new();
+        BufReader::new(f.unwrap()).read_to_string(&mut content)?;
+
+        match serde_json::from_str(&content) {
+            Ok(todo) => Ok(todo),
+            Err(why) => {
+                let offset: usize = content
+                    .split_inclusive('\n')
+                    .take(why.line().saturating_sub(1))
+                    .map(|line| line.len())
+                    .sum::<usize>()
+                    + why.column().saturating_sub(1);
+                let phrase = format!(
+                    "Error reading / opening file ::: {} (byte offset {})",
+                    why, offset
+                );
+                Err(std::io::Error::new(ErrorKind::InvalidData, phrase))
+            }
+        }
+    }
+
+    /// Save all the struct in a json file
+    /// The checksum of the items is stored with them
+    /// The items with an empty description are dropped, unless `prune_empty_on_save` is off
+    pub fn save_json(&mut self, filename: &str) -> Result<(), std::io::Error> {
+        if self.prune_empty_on_save {
+            let pruned = self.prune_empty();
+            if pruned > 0 {
+                eprintln!(
+                    "Warning: {} todo item(s) with an empty description were not saved",
+                    pruned
+                );
+            }
+        }
+        self.metadata.checksum = Some(self.checksum());
+        let path = format!("{}.json", filename);
+        let todo_list_json = serde_json::to_string_pretty(&self).unwrap();
+        write(path, &todo_list_json)
+    }
+
+    /// Keep a timestamped copy of the json file in the history directory,
+    /// before it gets overwritten
+    /// Only the `keep` most recent snapshots are kept, the others are pruned
+    pub fn snapshot_json(
+        filename: &str,
+        history_dir: &str,
+        keep: usize,
+    ) -> Result<(), std::io::Error> {
+        let path = format!("{}.json", filename);
+        if !Path::new(&path).exists() {
+            return Ok(());
+        }
+
+        create_dir_all(history_dir)?;
+        let name = Utc::now().format("%Y%m%d%H%M%S%9f");
+        copy(&path, Path::new(history_dir).join(format!("{}.json", name)))?;
+
+        for old in TodoList::snapshots(history_dir)?.iter().skip(keep) {
+            remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// All the snapshots in the history directory, from the most recent to the oldest
+    pub fn snapshots(history_dir: &str) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut snapshots: Vec<PathBuf> = match read_dir(history_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(why) if why.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(why) => return Err(why),
+        };
+        // The names are timestamps, so sorting them sorts by date
+        snapshots.sort();
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// Read the nth most recent snapshot, starting at 1
+    pub fn read_snapshot(history_dir: &str, nth: usize) -> Result<TodoList, std::io::Error> {
+        let snapshots = TodoList::snapshots(history_dir)?;
+        match nth.checked_sub(1).and_then(|index| snapshots.get(index)) {
+            Some(path) => TodoList::read_json(&path.with_extension("").to_string_lossy()),
+            None => Err(std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("There are only {} snapshot(s)", snapshots.len()),
+            )),
+        }
+    }
+
+    /// Save all the struct in a csv file
+    pub fn save_csv(&self, filename: &str) -> Result<(), std::io::Error> {
+        std::fs::write(format!("{}.csv", filename), self.to_csv_string())
+    }
+
+    /// Return all the items as a csv, with the header, sorted by id
+    pub fn to_csv_string(&self) -> String {
+        let mut items = self.items();
+        items.sort_by_key(|item| item.id);
+
+        let mut content = String::new();
+        content.push_str(&format!("{}\n", TodoItem::header_of_csv()));
+        for value in items {
+            let record = format!("{}\n", value.elem_in_csv());
+            content.push_str(&record);
+        }
+        content
+    }
+
+    /// Add the items of a csv file, without extension, in the order of its lines
+    /// or in the reverse order if `reverse` is given, so they get the next ids in that order
+    /// Items with the same description as an existing one are skipped,
+    /// and so are the lines that can't be parsed, with a warning
+    pub fn import_csv(
+        &mut self,
+        filename: &str,
+        reverse: bool,
+    ) -> Result<MergeReport, std::io::Error> {
+        let content = std::fs::read_to_string(format!("{}.csv", filename))?;
+        let mut items: Vec<TodoItem> = content
+            .lines()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match item_from_csv_line(line) {
+                Ok(item) => Some(item),
+                Err(why) => {
+                    eprintln!("Warning: {}, skipping it", why);
+                    None
+                }
+            })
+            .collect();
+        if reverse {
+            items.reverse();
+        }
+
+        let mut imported = TodoList::build();
+        for item in items {
+            imported
+                .list
+                .entry(item.description.to_ascii_lowercase())
+                .or_insert(item);
+        }
+        Ok(self.merge(imported, MergeStrategy::Skip))
+    }
+
+    /// Read the default file, and return the all struct
+    /// In this case the file is CSV
+    /// A missing file gives a `NotFound` error, without creating it
+    /// The items are sorted by id, compared as numbers and not as text
+    pub fn read_csv(filename: &str) -> Result<TodoList, std::io::Error> {
+        let content = std::fs::read_to_string(format!("{}.csv", filename))?;
+        let mut map: IndexMap<String, TodoItem> = items_from_csv(&content)?
+            .into_iter()
+            .map(|item| (item.description.to_ascii_lowercase(), item))
+            .collect();
+        map.sort_by(|_, a, _, b| a.id.cmp(&b.id));
+
+        let next_id = map.values().map(|item| item.id + 1).max().unwrap_or(0);
+        Ok(TodoList::from_parts(map, next_id, Metadata::default()))
+    }
+}
+
+/// Parse the items of a csv, with the header, in the order of the lines
+/// A line with an id that is not a number gives an `InvalidData` error
+fn items_from_csv(content: &str) -> Result<Vec<TodoItem>, std::io::Error> {
+    content
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(item_from_csv_line)
+        .collect()
+}
+
+/// Parse one line of a csv, filling in the fields it misses
+fn item_from_csv_line(line: &str) -> Result<TodoItem, std::io::Error> {
+    let mut v = split_csv_line(line);
+    // Legacy lines may miss the done column or even the description
+    if v.len() < 3 {
+        eprintln!(
+            "Warning: csv line \"{}\" has only {} field(s), filling in the missing ones",
+            line,
+            v.len()
+        );
+    }
+    // and old unquoted descriptions may have commas
+    if v.len() > 3 {
+        let done = v.pop().unwrap_or_default();
+        let description = v.split_off(1).join(",");
+        v.push(description);
+        v.push(done);
+    }
+    let mut v = v.into_iter();
+    let (id, description, done) = (
+        v.next().unwrap_or_default(),
+        v.next().unwrap_or_default(),
+        v.next().unwrap_or(String::from("false")),
+    );
+    let id: u32 = id.trim().parse().map_err(|why| {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("csv line \"{}\" has an invalid id: {}", line, why),
+        )
+    })?;
+
+    Ok(TodoItem {
+        id,
+        description,
+        done: done.trim() == "true",
+        created_at: None,
+        completed_at: None,
+        owner: None,
+        history: VecDeque::new(),
+        tags: BTreeSet::new(),
+        due: None,
+        priority: Priority::default(),
+        completion_note: None,
+        progress: 0,
+        actual_minutes: None,
+        url: None,
+    })
+}
+
+/// Quote a field of a csv, if it has commas, quotes or line breaks
+fn quote_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// Split a line of a csv into its fields, unquoting the quoted ones
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// An empty directory of its own for each test, as they run in parallel
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-lib-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file(dir: &std::path::Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn csv_line_with_two_fields_is_pending() {
+        let filename = file(&temp_dir("short-csv"), "list");
+        std::fs::write(
+            format!("{}.csv", filename),
+            "Id,Description,Done\n0,buy milk\n1\n",
+        )
+        .unwrap();
+        let todo = TodoList::read_csv(&filename).unwrap();
+        let item = todo.get_item_by_id(0).unwrap();
+        assert_eq!(item.description(), "buy milk");
+        assert!(!item.is_done());
+        assert_eq!(todo.get_item_by_id(1).unwrap().description(), "");
+    }
+
+    fn list_of(descriptions: &[&str]) -> TodoList {
+        let mut todo = TodoList::build();
+        for description in descriptions {
+            todo.insert(String::from(*description));
+        }
+        todo
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn average_completion_time_of_synthetic_history() {
+        let mut todo = list_of(&["a", "b", "c", "d"]);
+        let mut complete = |description: &str, hours| {
+            let item = todo.list.get_mut(description).unwrap();
+            item.created_at = Some(at(2024, 1, 1));
+            item.completed_at = Some(at(2024, 1, 1) + Duration::hours(hours));
+            item.done = true;
+        };
+        complete("a", 1);
+        complete("b", 2);
+        assert_eq!(todo.avg_completion_time(), None);
+
+        let item = todo.list.get_mut("c").unwrap();
+        item.created_at = Some(at(2024, 1, 1));
+        item.completed_at = Some(at(2024, 1, 1) + Duration::hours(3));
+        item.done = true;
+        assert_eq!(todo.avg_completion_time(), Some(Duration::hours(2)));
+        // one pending item left, at two hours each
+        assert_eq!(
+            todo.forecast(at(2024, 2, 1)),
+            Some(at(2024, 2, 1) + Duration::hours(2))
+        );
+    }
+
+    fn ids(items: &[&TodoItem]) -> Vec<u32> {
+        items.iter().map(|item| item.id()).collect()
+    }
+
+    #[test]
+    fn case_sensitive_search() {
+        let todo = list_of(&["Deploy app", "deploy db"]);
+        assert_eq!(ids(&todo.search("Deploy", true)), vec![0]);
+        assert_eq!(ids(&todo.search("Deploy", false)), vec![0, 1]);
+    }
+
+    #[test]
+    fn rollback_two_snapshots() {
+        let dir = temp_dir("rollback");
+        let filename = file(&dir, "list");
+        let history = file(&dir, "history");
+        let mut todo = TodoList::build();
+        for description in ["a", "b", "c"] {
+            TodoList::snapshot_json(&filename, &history, 10).unwrap();
+            todo.insert(String::from(description));
+            todo.save_json(&filename).unwrap();
+        }
+
+        let items = |nth| TodoList::read_snapshot(&history, nth).map(|todo| todo.items().len());
+        assert_eq!(TodoList::snapshots(&history).unwrap().len(), 2);
+        assert_eq!(items(1).unwrap(), 2);
+        assert_eq!(items(2).unwrap(), 1);
+        assert!(items(3).is_err());
+    }
+
+    #[test]
+    fn partition_puts_each_item_in_one_list() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.update_todo_item_id(1);
+        let (done, pending) = todo.partition();
+        assert_eq!(ids(&done.items()), vec![1]);
+        assert_eq!(ids(&pending.items()), vec![0, 2]);
+        assert_eq!(done.next_id, 3);
+    }
+
+    #[test]
+    fn filter_by_owner() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.assign(1, Some(String::from("ana")));
+        assert_eq!(ids(&todo.filter_by_owner("ana")), vec![1]);
+        assert!(todo.filter_by_owner("rui").is_empty());
+    }
+
+    #[test]
+    fn insert_at_the_top() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.insert_at(String::from("c"), 0);
+        assert_eq!(ids(&todo.items()), vec![2, 0, 1]);
+        todo.remove_by_id(0);
+        assert_eq!(ids(&todo.items()), vec![2, 1]);
+    }
+
+    #[test]
+    fn history_cap_evicts_the_oldest_events() {
+        let mut todo = list_of(&["a"]);
+        todo.set_max_history(2);
+        todo.assign(0, Some(String::from("ana")));
+        todo.update_todo_item_id(0);
+        let history = todo.get_item_by_id(0).unwrap().history();
+        let events: Vec<&str> = history.iter().map(|event| event.event()).collect();
+        assert_eq!(events, vec!["assigned to ana", "completed"]);
+
+        todo.set_max_history(1);
+        assert_eq!(todo.get_item_by_id(0).unwrap().history().len(), 1);
+    }
+
+    #[test]
+    fn checksum_is_stable_and_changes_on_mutation() {
+        let mut todo = list_of(&["a", "b"]);
+        let reloaded: TodoList = serde_json::from_str(&todo.to_json().unwrap()).unwrap();
+        assert_eq!(todo.checksum(), todo.checksum());
+        assert_eq!(todo.checksum(), reloaded.checksum());
+
+        let before = todo.checksum();
+        todo.update_todo_item_id(0);
+        assert_ne!(todo.checksum(), before);
+    }
+
+    #[test]
+    fn saved_checksum_is_verified() {
+        let filename = file(&temp_dir("checksum"), "list");
+        list_of(&["a"]).save_json(&filename).unwrap();
+        assert!(TodoList::read_json(&filename).unwrap().verify_checksum());
+
+        let path = format!("{}.json", filename);
+        let edited = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"a\"", "\"b\"");
+        std::fs::write(&path, edited).unwrap();
+        assert!(!TodoList::read_json(&filename).unwrap().verify_checksum());
+    }
+
+    #[test]
+    fn markdown_with_and_without_ids() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.update_todo_item_id(1);
+        assert_eq!(todo.to_markdown(true), "- [ ] #0 a\n- [x] #1 b\n");
+        assert_eq!(todo.to_markdown(false), "- [ ] a\n- [x] b\n");
+    }
+
+    fn item_mut(todo: &mut TodoList, id: u32) -> &mut TodoItem {
+        todo.list.values_mut().find(|item| item.id == id).unwrap()
+    }
+
+    #[test]
+    fn timeline_counts_land_in_their_weeks() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        for (id, day) in [(0, 2), (1, 3), (2, 10)] {
+            item_mut(&mut todo, id).completed_at = Some(at(2024, 1, day));
+        }
+        let timeline =
+            todo.stats_timeline(at(2024, 1, 1).date_naive(), at(2024, 1, 20).date_naive());
+        let counts: Vec<(u32, usize)> = timeline
+            .iter()
+            .map(|(week, count)| (week.week(), *count))
+            .collect();
+        assert_eq!(counts, vec![(1, 2), (2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn remove_completed_keeps_the_pending() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.update_todo_item_id(0);
+        todo.update_todo_item_id(2);
+        assert_eq!(todo.remove_completed(), 2);
+        assert_eq!(ids(&todo.items()), vec![1]);
+        assert!(todo.get_item_by_id(0).is_none());
+        assert_eq!(todo.remove_completed(), 0);
+    }
+
+    #[test]
+    fn malformed_json_is_invalid_data() {
+        let filename = file(&temp_dir("malformed"), "list");
+        std::fs::write(format!("{}.json", filename), "{\n  \"list\": oops").unwrap();
+        let why = TodoList::read_json(&filename).unwrap_err();
+        assert_eq!(why.kind(), ErrorKind::InvalidData);
+        assert!(why.to_string().contains("byte offset 12"), "{}", why);
+    }
+
+    #[test]
+    fn all_tags_are_deduped() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.tag(0, String::from("work"));
+        todo.tag(1, String::from("work"));
+        todo.tag(1, String::from("home"));
+        let tags: Vec<String> = todo.all_tags().into_iter().collect();
+        assert_eq!(tags, vec!["home", "work"]);
+        let counts: Vec<(String, usize)> = todo.tag_counts().into_iter().collect();
+        assert_eq!(
+            counts,
+            vec![(String::from("home"), 1), (String::from("work"), 2)]
+        );
+        assert!(!todo.tag(9, String::from("work")));
+    }
+
+    #[test]
+    fn eviction_past_the_cap() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.update_todo_item_id(1);
+        todo.set_max_items(Some(2));
+        let evicted = todo.evict_over_capacity();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].description(), "b");
+
+        todo.insert(String::from("d"));
+        let evicted = todo.evict_over_capacity();
+        assert_eq!(evicted[0].description(), "a");
+        assert_eq!(ids(&todo.items()), vec![2, 3]);
+    }
+
+    #[test]
+    fn eviction_keeps_the_given_ids_for_last() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.set_max_items(Some(2));
+        todo.insert_with_state(String::from("c"), 2, true);
+        todo.insert_with_state(String::from("d"), 3, true);
+        todo.insert_with_state(String::from("e"), 4, true);
+        let evicted = todo.evict_over_capacity_keeping(&[2, 3, 4]);
+        let evicted: Vec<&str> = evicted.iter().map(|item| item.description()).collect();
+        assert_eq!(evicted, vec!["a", "b", "c"]);
+        assert_eq!(ids(&todo.items()), vec![3, 4]);
+    }
+
+    #[test]
+    fn get_many_keeps_the_order_and_skips_absent() {
+        let todo = list_of(&["a", "b", "c"]);
+        assert_eq!(ids(&todo.get_many_by_ids(&[2, 9, 0])), vec![2, 0]);
+    }
+
+    #[test]
+    fn lookup_by_id_after_inserts_and_removes() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.remove_by_id(1);
+        todo.remove_by_description(String::from("C"));
+        todo.insert(String::from("d"));
+        assert!(todo.get_item_by_id(1).is_none());
+        assert!(todo.get_item_by_id(2).is_none());
+        assert_eq!(todo.get_item_by_id(0).unwrap().description(), "a");
+        assert_eq!(todo.get_item_by_id(3).unwrap().description(), "d");
+
+        let reloaded: TodoList = serde_json::from_str(&todo.to_json().unwrap()).unwrap();
+        assert_eq!(reloaded.get_item_by_id(3).unwrap().description(), "d");
+    }
+
+    #[test]
+    fn compact_a_messy_list() {
+        let mut todo = list_of(&[" a ", "x", "A", "y"]);
+        todo.remove_by_id(1);
+        let report = todo.compact();
+        assert_eq!(report.trimmed, 1);
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].id(), 2);
+        assert_eq!(report.renumbered, 1);
+        assert_eq!(ids(&todo.items()), vec![0, 1]);
+        assert_eq!(todo.get_item_by_id(1).unwrap().description(), "y");
+        assert!(todo.compact().is_empty());
+    }
+
+    #[test]
+    fn summary_of_one_and_several_items() {
+        let mut todo = list_of(&["a"]);
+        assert_eq!(todo.summary_string(), "1 item, 0 done, 1 pending");
+        todo.insert(String::from("b"));
+        todo.update_todo_item_id(0);
+        assert_eq!(todo.summary_string(), "2 items, 1 done, 1 pending");
+        assert_eq!(
+            TodoList::build().summary_string(),
+            "0 items, 0 done, 0 pending"
+        );
+    }
+
+    #[test]
+    fn query_matches_all_the_filters() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.tag(0, String::from("x"));
+        todo.tag(2, String::from("x"));
+        todo.update_todo_item_id(2);
+        todo.assign(0, Some(String::from("ana")));
+        let query = Query {
+            done: Some(false),
+            tag: Some(String::from("x")),
+            ..Query::default()
+        };
+        assert_eq!(ids(&todo.query(&query)), vec![0]);
+        let query = Query {
+            owner: Some(String::from("rui")),
+            ..query
+        };
+        assert!(todo.query(&query).is_empty());
+    }
+
+    #[test]
+    fn diff_json_structure() {
+        let old = list_of(&["a", "b"]);
+        let mut new: TodoList = serde_json::from_str(&old.to_json().unwrap()).unwrap();
+        assert!(old.diff(&new).is_empty());
+        new.update_todo_item_id(0);
+        new.remove_by_id(1);
+        new.insert(String::from("c"));
+
+        let diff = serde_json::to_value(old.diff(&new)).unwrap();
+        assert_eq!(diff["added"][0]["description"], "c");
+        assert_eq!(diff["removed"][0]["id"], 1);
+        assert_eq!(diff["changed"][0]["before"]["done"], false);
+        assert_eq!(diff["changed"][0]["after"]["done"], true);
+    }
+
+    #[test]
+    fn priority_steps_stop_at_the_limits() {
+        let mut todo = list_of(&["a"]);
+        assert_eq!(
+            todo.adjust_priority(0, 1),
+            Some((Priority::Medium, Priority::High))
+        );
+        assert_eq!(
+            todo.adjust_priority(0, 1),
+            Some((Priority::High, Priority::High))
+        );
+        assert_eq!(
+            todo.adjust_priority(0, -2),
+            Some((Priority::High, Priority::Low))
+        );
+        assert_eq!(todo.adjust_priority(9, 1), None);
+        assert_eq!(" HIGH".parse(), Ok(Priority::High));
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn csv_string_has_the_header_and_a_row_per_item() {
+        let todo = list_of(&["a", "b, \"c\""]);
+        let csv = todo.to_csv_string();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], TodoItem::header_of_csv());
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("0,a,"));
+        assert!(lines[2].starts_with("1,\"b, \"\"c\"\"\","));
+        assert_eq!(split_csv_line(lines[2])[1], "b, \"c\"");
+    }
+
+    #[test]
+    fn completion_note_is_cleared_on_reopen() {
+        let mut todo = list_of(&["a"]);
+        todo.complete_by_id(0, Some(String::from("shipped")));
+        assert_eq!(
+            todo.get_item_by_id(0).unwrap().completion_note(),
+            Some("shipped")
+        );
+        todo.reopen_by_id(0);
+        assert_eq!(todo.get_item_by_id(0).unwrap().completion_note(), None);
+    }
+
+    #[test]
+    fn random_pick_with_a_fixed_seed() {
+        let mut todo = list_of(&["a", "b", "c", "d"]);
+        todo.complete_by_id(0, None);
+        let pick = |todo: &TodoList, seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            todo.random_pending(&mut rng).map(|item| item.id())
+        };
+        assert_eq!(pick(&todo, 7), pick(&todo, 7));
+        assert_ne!(pick(&todo, 7), Some(0));
+
+        for id in 1..4 {
+            todo.complete_by_id(id, None);
+        }
+        assert_eq!(pick(&todo, 7), None);
+    }
+
+    #[test]
+    fn rename_a_tag_used_on_several_items() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.tag(0, String::from("wip"));
+        todo.tag(1, String::from("wip"));
+        todo.tag(2, String::from("other"));
+        assert_eq!(todo.rename_tag("wip", "doing"), 2);
+        let tags: Vec<String> = todo.all_tags().into_iter().collect();
+        assert_eq!(tags, vec!["doing", "other"]);
+    }
+
+    #[test]
+    fn next_task_tie_breaks() {
+        let mut todo = list_of(&["a", "b", "c", "d", "e"]);
+        for id in 0..5 {
+            todo.get_mut_by_id(id).unwrap().created_at = Some(at(2024, 1, 1));
+        }
+        // 1. higher priority first
+        todo.set_priority(4, Priority::High);
+        assert_eq!(todo.next_task().unwrap().id(), 4);
+        todo.complete_by_id(4, None);
+
+        // 2. earlier due date first, and no due date last
+        todo.set_due(3, Some(at(2024, 3, 1).date_naive()));
+        todo.set_due(2, Some(at(2024, 2, 1).date_naive()));
+        assert_eq!(todo.next_task().unwrap().id(), 2);
+        todo.complete_by_id(2, None);
+        assert_eq!(todo.next_task().unwrap().id(), 3);
+        todo.complete_by_id(3, None);
+
+        // 3. earlier creation first
+        todo.get_mut_by_id(1).unwrap().created_at = Some(at(2023, 12, 1));
+        assert_eq!(todo.next_task().unwrap().id(), 1);
+
+        // 4. lower id first
+        todo.get_mut_by_id(1).unwrap().created_at = Some(at(2024, 1, 1));
+        assert_eq!(todo.next_task().unwrap().id(), 0);
+    }
+
+    #[test]
+    fn streamed_json_matches_the_buffered_one() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.tag(1, String::from("x"));
+        let mut streamed = Vec::new();
+        todo.items_json_stream(&mut streamed).unwrap();
+        assert_eq!(streamed, todo.items_json().unwrap().into_bytes());
+
+        let mut empty = Vec::new();
+        TodoList::build().items_json_stream(&mut empty).unwrap();
+        assert_eq!(empty, b"[]");
+    }
+
+    #[test]
+    fn remove_matching_a_query() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.tag(0, String::from("x"));
+        todo.tag(2, String::from("x"));
+        todo.complete_by_id(2, None);
+        let query = Query {
+            done: Some(false),
+            tag: Some(String::from("x")),
+            ..Query::default()
+        };
+        let removed = todo.remove_matching(&query);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id(), 0);
+        assert_eq!(ids(&todo.items()), vec![1, 2]);
+    }
+
+    #[test]
+    fn completion_time_buckets_of_synthetic_durations() {
+        let mut todo = list_of(&["a", "b", "c", "d", "e", "f"]);
+        for (id, hours) in [(0, 12), (1, 48), (2, 60), (3, 120), (4, 240)] {
+            let item = todo.get_mut_by_id(id).unwrap();
+            item.created_at = Some(at(2024, 1, 1));
+            item.completed_at = Some(at(2024, 1, 1) + Duration::hours(hours));
+        }
+        assert_eq!(
+            todo.completion_time_buckets(),
+            vec![("<1d", 1), ("1-3d", 2), ("3-7d", 1), (">7d", 1)]
+        );
+    }
+
+    #[test]
+    fn insert_already_completed() {
+        let mut todo = TodoList::build();
+        assert!(todo.insert_with_state(String::from("a"), 0, true));
+        let item = todo.get_item_by_id(0).unwrap();
+        assert!(item.is_done());
+        assert!(item.completed_at().is_some());
+    }
+
+    #[test]
+    fn empty_items_are_pruned_on_save() {
+        let dir = temp_dir("prune");
+        let filename = file(&dir, "list");
+        let saved = || {
+            let todo = TodoList::read_json(&filename).unwrap();
+            let mut descriptions: Vec<String> = todo
+                .items()
+                .iter()
+                .map(|item| String::from(item.description()))
+                .collect();
+            descriptions.sort();
+            descriptions
+        };
+        let mut todo = list_of(&["a", "  "]);
+        todo.save_json(&filename).unwrap();
+        assert_eq!(saved(), vec!["a"]);
+
+        let mut todo = list_of(&["a", "  "]);
+        todo.set_prune_empty_on_save(false);
+        todo.save_json(&filename).unwrap();
+        assert_eq!(saved(), vec!["  ", "a"]);
+    }
+
+    #[test]
+    fn csv_ids_sort_as_numbers() {
+        let dir = temp_dir("csv-ids");
+        let filename = file(&dir, "list");
+        std::fs::write(
+            format!("{}.csv", filename),
+            "Id,Description,Done\n10,ten,false\n2,two,false\n",
+        )
+        .unwrap();
+        let todo = TodoList::read_csv(&filename).unwrap();
+        assert_eq!(ids(&todo.items()), vec![2, 10]);
+        assert_eq!(todo.next_id, 11);
+    }
+
+    #[test]
+    fn single_item_json() {
+        let mut todo = list_of(&["a"]);
+        todo.set_priority(0, Priority::High);
+        let json: serde_json::Value =
+            serde_json::from_str(&todo.get_item_by_id(0).unwrap().to_json().unwrap()).unwrap();
+        assert_eq!(json["id"], 0);
+        assert_eq!(json["description"], "a");
+        assert_eq!(json["done"], false);
+        assert_eq!(json["priority"], "high");
+        assert!(json.get("tags").is_none());
+    }
+
+    /// A list with "a" pending and a list with "a" done and created later, besides a new "b"
+    fn conflicting_lists() -> (TodoList, TodoList) {
+        let mut base = list_of(&["x", "a"]);
+        base.get_mut_by_id(1).unwrap().created_at = Some(at(2024, 1, 1));
+        let mut other = list_of(&["A", "b"]);
+        other.get_mut_by_id(0).unwrap().created_at = Some(at(2024, 2, 1));
+        other.complete_by_id(0, None);
+        (base, other)
+    }
+
+    #[test]
+    fn merge_skip_keeps_the_existing() {
+        let (mut base, other) = conflicting_lists();
+        let report = base.merge(other, MergeStrategy::Skip);
+        assert_eq!((report.added, report.replaced, report.skipped), (1, 0, 1));
+        assert!(!base.get_item_by_id(1).unwrap().is_done());
+        assert_eq!(base.get_item_by_id(2).unwrap().description(), "b");
+    }
+
+    #[test]
+    fn merge_keep_newer_replaces_with_the_newer() {
+        let (mut base, other) = conflicting_lists();
+        let report = base.merge(other, MergeStrategy::KeepNewer);
+        assert_eq!((report.added, report.replaced, report.skipped), (1, 1, 0));
+        let item = base.get_item_by_id(1).unwrap();
+        assert_eq!(item.description(), "A");
+
+        let (base, mut other) = conflicting_lists();
+        let report = other.merge(base, MergeStrategy::KeepNewer);
+        assert_eq!(report.replaced, 0);
+    }
+
+    #[test]
+    fn merge_keep_done_replaces_with_the_completed() {
+        let (mut base, other) = conflicting_lists();
+        let report = base.merge(other, MergeStrategy::KeepDone);
+        assert_eq!(report.replaced, 1);
+        assert!(base.get_item_by_id(1).unwrap().is_done());
+
+        let (base, mut other) = conflicting_lists();
+        assert_eq!(other.merge(base, MergeStrategy::KeepDone).replaced, 0);
+    }
+
+    #[test]
+    fn merge_strategy_names() {
+        assert_eq!("keep-newer".parse(), Ok(MergeStrategy::KeepNewer));
+        assert_eq!("DONE".parse(), Ok(MergeStrategy::KeepDone));
+        assert!("newest".parse::<MergeStrategy>().is_err());
+    }
+
+    #[test]
+    fn since_id_only_gives_newer_items() {
+        let todo = list_of(&["a", "b", "c"]);
+        let query = Query {
+            since_id: Some(0),
+            ..Query::default()
+        };
+        assert_eq!(ids(&todo.query(&query)), vec![1, 2]);
+    }
+
+    #[test]
+    fn load_or_default_on_missing_and_corrupt_files() {
+        let dir = temp_dir("load");
+        let missing = file(&dir, "missing");
+        assert!(TodoList::load_or_default(&missing, Format::Csv).is_empty());
+        assert!(TodoList::load_or_default(&missing, Format::Json).is_empty());
+        assert!(!Path::new(&format!("{}.csv", missing)).exists());
+        assert_eq!(
+            TodoList::load(&missing, Format::Csv).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        let corrupt = file(&dir, "corrupt");
+        write(
+            format!("{}.csv", corrupt),
+            "Id,Description,Done\nabc,foo,true\n",
+        )
+        .unwrap();
+        write(format!("{}.json", corrupt), "{ not json").unwrap();
+        assert!(TodoList::load_or_default(&corrupt, Format::Csv).is_empty());
+        assert!(TodoList::load_or_default(&corrupt, Format::Json).is_empty());
+        assert_eq!(
+            TodoList::load(&corrupt, Format::Json).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn descriptions_follow_the_ids() {
+        let mut todo = list_of(&["a", "b"]);
+        todo.insert_at(String::from("c"), 0);
+        assert_eq!(todo.descriptions(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn anonymized_replaces_the_text_only() {
+        let mut todo = list_of(&["secret plan"]);
+        todo.tag(0, String::from("work"));
+        todo.set_priority(0, Priority::High);
+        todo.set_url(0, Some(String::from("https://example.com/secret-plan")));
+        todo.complete_by_id(0, Some(String::from("done in secret")));
+
+        let anonymized = todo.anonymized(false, false);
+        let item = anonymized.get_item_by_id(0).unwrap();
+        assert_eq!(item.description(), "task-0");
+        assert!(item.is_done());
+        assert_eq!(item.priority(), Priority::High);
+        assert!(item.tags().contains("work"));
+        assert_eq!(item.completion_note(), None);
+        assert_eq!(item.url(), None);
+        assert!(!anonymized.to_json().unwrap().contains("secret"));
+        assert!(anonymized
+            .anonymized(true, true)
+            .get_item_by_id(0)
+            .unwrap()
+            .tags()
+            .is_empty());
+    }
+
+    #[test]
+    fn progress_completes_and_reopens() {
+        let mut todo = list_of(&["a"]);
+        todo.set_progress(0, 60);
+        assert!(!todo.get_item_by_id(0).unwrap().is_done());
+        todo.set_progress(0, 120);
+        let item = todo.get_item_by_id(0).unwrap();
+        assert_eq!(item.progress(), FULL_PROGRESS);
+        assert!(item.is_done());
+        todo.set_progress(0, 90);
+        assert!(!todo.get_item_by_id(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn tag_cloud_counts_and_order() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        for (id, tag) in [(0, "x"), (1, "x"), (0, "z"), (1, "z"), (2, "y")] {
+            todo.tag(id, String::from(tag));
+        }
+        assert_eq!(
+            todo.tag_cloud(),
+            vec![
+                (String::from("x"), 2),
+                (String::from("z"), 2),
+                (String::from("y"), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn import_csv_in_reverse() {
+        let dir = temp_dir("import");
+        let filename = file(&dir, "in");
+        write(
+            format!("{}.csv", filename),
+            "Id,Description,Done\n0,a,false\nx,bad,false\n1,b,true\n2,c,false\n",
+        )
+        .unwrap();
+        let mut todo = list_of(&["b"]);
+        let report = todo.import_csv(&filename, true).unwrap();
+        assert_eq!((report.added, report.skipped), (2, 1));
+        assert_eq!(todo.get_item_by_id(1).unwrap().description(), "c");
+        assert_eq!(todo.get_item_by_id(2).unwrap().description(), "a");
+    }
+
+    #[test]
+    fn overdue_and_completed_before() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        let today = at(2024, 1, 10).date_naive();
+        todo.set_due(0, Some(at(2024, 1, 9).date_naive()));
+        todo.set_due(1, Some(at(2024, 1, 10).date_naive()));
+        todo.set_due(2, Some(at(2024, 1, 1).date_naive()));
+        todo.complete_by_id(2, None);
+        assert_eq!(ids(&todo.overdue(today)), vec![0]);
+
+        todo.get_mut_by_id(2).unwrap().completed_at = Some(at(2024, 1, 1));
+        assert_eq!(ids(&todo.completed_before(at(2024, 1, 2))), vec![2]);
+        assert!(todo.completed_before(at(2023, 12, 31)).is_empty());
+    }
+
+    #[test]
+    fn paging_in_two_calls() {
+        let todo = list_of(&["a", "b", "c", "d", "e"]);
+        let limit = NonZeroUsize::new(3).unwrap();
+        let (page, cursor) = todo.split_at_id(None, limit);
+        assert_eq!(ids(&page), vec![0, 1, 2]);
+        assert_eq!(cursor, Some(2));
+        let (page, cursor) = todo.split_at_id(cursor, limit);
+        assert_eq!(ids(&page), vec![3, 4]);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn actual_minutes_are_recorded() {
+        let mut todo = list_of(&["a"]);
+        assert!(todo.set_actual_minutes(0, 90));
+        assert_eq!(todo.get_item_by_id(0).unwrap().actual_minutes(), Some(90));
+        assert!(!todo.set_actual_minutes(1, 90));
+    }
+
+    #[test]
+    fn duplicates_ignore_case_and_whitespace() {
+        let mut todo = list_of(&["Buy  milk", "walk dog"]);
+        todo.insert(String::from(" buy milk"));
+        assert_eq!(
+            todo.find_duplicates(),
+            vec![(String::from("buy milk"), vec![0, 2])]
+        );
+    }
+
+    #[test]
+    fn validation_errors_of_a_broken_list() {
+        let broken: TodoList = serde_json::from_str(
+            r#"{"list":{" ":{"id":0,"description":" ","done":false},"a":{"id":5,"description":"a","done":false}},"next_id":1}"#,
+        )
+        .unwrap();
+        let errors = broken.validate();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::EmptyDescription { id: 0 },
+                ValidationError::IdNotBelowNext { id: 5, next_id: 1 },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&errors[1]).unwrap(),
+            serde_json::json!({"kind": "id_not_below_next", "id": 5, "next_id": 1})
+        );
+        assert!(list_of(&["a"]).validate().is_empty());
+    }
+
+    #[test]
+    fn move_all_completed_to_another_list() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.complete_by_id(0, None);
+        todo.complete_by_id(2, None);
+        let mut target = list_of(&["C"]);
+
+        assert_eq!(todo.move_all_completed_to(&mut target), (1, 1));
+        assert_eq!(ids(&todo.items()), vec![1, 2]);
+        let moved = target.get_item_by_id(1).unwrap();
+        assert_eq!(moved.description(), "a");
+        assert!(moved.is_done());
+        assert!(target.validate().is_empty());
+    }
+
+    #[test]
+    fn url_is_kept_in_the_file() {
+        let mut todo = list_of(&["review"]);
+        todo.set_url(0, Some(String::from("https://example.com/pull/1")));
+        let reloaded: TodoList = serde_json::from_str(&todo.to_json().unwrap()).unwrap();
+        assert_eq!(
+            reloaded.get_item_by_id(0).unwrap().url(),
+            Some("https://example.com/pull/1")
+        );
+    }
+
+    #[test]
+    fn two_serializations_are_identical() {
+        let mut todo = list_of(&["a", "b", "c"]);
+        todo.insert_at(String::from("d"), 0);
+        let json = todo.to_json().unwrap();
+        assert_eq!(json, todo.to_json().unwrap());
+
+        let reloaded: TodoList = serde_json::from_str(&json).unwrap();
+        assert_eq!(json, reloaded.to_json().unwrap());
+        assert_eq!(ids(&reloaded.items()), vec![3, 0, 1, 2]);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let saved: Vec<u64> = value["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_u64().unwrap())
+            .collect();
+        assert_eq!(saved, vec![0, 1, 2, 3]);
+        assert!(list_of(&["a"]).to_json().unwrap().find("order").is_none());
+    }
+
+    #[test]
+    fn old_map_form_is_still_read() {
+        let todo: TodoList = serde_json::from_str(
+            r#"{"list":{"b":{"id":1,"description":"b","done":false},"a":{"id":0,"description":"a","done":true}},"next_id":2}"#,
+        )
+        .unwrap();
+        assert_eq!(ids(&todo.items()), vec![1, 0]);
+        assert!(todo.get_item_by_id(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn csv_line_with_invalid_id_is_invalid_data() {
+        let why = items_from_csv("Id,Description,Done\nabc,foo,true\n").unwrap_err();
+        assert_eq!(why.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn descriptions_only_differing_by_case_are_kept() {
+        let todo: TodoList = serde_json::from_str(
+            r#"{"items":[{"id":0,"description":"a","done":false},{"id":1,"description":"A","done":false}],"next_id":2}"#,
+        )
+        .unwrap();
+        assert_eq!(todo.len(), 2);
+        assert!(todo
+            .validate()
+            .contains(&ValidationError::DuplicateDescription {
+                description: String::from("a"),
+                ids: vec![0, 1],
+            }));
+    }
 }